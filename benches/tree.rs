@@ -0,0 +1,67 @@
+use criterion::{BenchmarkId, Criterion, Throughput, criterion_group, criterion_main};
+use ptlist::{ColorTheme, FileEntry, Renderer, TreeRenderer};
+use std::hint::black_box;
+use std::io;
+use std::path::PathBuf;
+use termcolor::NoColor;
+
+/// A recursive listing of `files` files in depth-first order, 100 files per
+/// directory and 20 directories per parent, like `--recursive` produces
+fn listing(files: usize) -> Vec<FileEntry> {
+    let mut entries = Vec::with_capacity(files + files / 100 + files / 2000 + 2);
+    let mut remaining = files;
+    for outer in 0.. {
+        if remaining == 0 {
+            break;
+        }
+        let outer_dir = PathBuf::from(format!("root/group{outer:04}"));
+        entries.push(FileEntry::new(outer_dir.clone(), true, 4096, None, None));
+        for inner in 0..20 {
+            if remaining == 0 {
+                break;
+            }
+            let dir = outer_dir.join(format!("dir{inner:02}"));
+            entries.push(FileEntry::new(dir.clone(), true, 4096, None, None));
+            for file in 0..remaining.min(100) {
+                let path = dir.join(format!("file{file:03}.txt"));
+                entries.push(FileEntry::new(path, false, file as u64 * 37, None, None));
+            }
+            remaining = remaining.saturating_sub(100);
+        }
+    }
+    entries
+}
+
+fn tree_render(c: &mut Criterion) {
+    let theme = ColorTheme::new(false);
+    let mut group = c.benchmark_group("tree_render");
+    group.sample_size(10);
+
+    for files in [1_000, 10_000, 100_000, 200_000] {
+        let entries = listing(files);
+        group.throughput(Throughput::Elements(entries.len() as u64));
+
+        group.bench_with_input(BenchmarkId::new("plain", files), &entries, |b, entries| {
+            let renderer = TreeRenderer::new(&theme);
+            b.iter(|| {
+                let mut out = NoColor::new(io::sink());
+                renderer.render(black_box(entries), &mut out).unwrap();
+            });
+        });
+
+        group.bench_with_input(BenchmarkId::new("totals", files), &entries, |b, entries| {
+            let renderer = TreeRenderer::new(&theme)
+                .dir_totals(true)
+                .collapse(Some(50))
+                .dirs_first(true);
+            b.iter(|| {
+                let mut out = NoColor::new(io::sink());
+                renderer.render(black_box(entries), &mut out).unwrap();
+            });
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, tree_render);
+criterion_main!(benches);
@@ -0,0 +1,118 @@
+use crate::file_entry::FileEntry;
+use crate::tree::{DirTotals, EntryTree};
+
+/// Narrowest and widest size bar, in terminal columns
+const MIN_BAR_WIDTH: usize = 10;
+const MAX_BAR_WIDTH: usize = 40;
+/// Partial blocks from one to seven eighths of a column
+const PARTIAL_BLOCKS: [char; 7] = ['▏', '▎', '▍', '▌', '▋', '▊', '▉'];
+
+/// Sizes for `--bars`: directories count every file listed below them, or their own
+/// size when nothing below them is listed, and each entry's share is relative to
+/// the directory that contains it
+#[derive(Debug)]
+pub struct SizeShares {
+    totals: Vec<DirTotals>,
+    shares: Vec<f64>,
+}
+
+impl SizeShares {
+    pub fn new(entries: &[FileEntry]) -> Self {
+        Self::from_tree(entries, &EntryTree::new(entries))
+    }
+
+    pub fn from_tree(entries: &[FileEntry], tree: &EntryTree) -> Self {
+        let totals = tree.listed_totals(entries);
+        let root_size: u64 = tree.roots().iter().map(|&n| totals[n].size).sum();
+        let shares = (0..entries.len())
+            .map(|node| {
+                let parent_size = match tree.parent(node) {
+                    Some(parent) => totals[parent].size,
+                    None => root_size,
+                };
+                if parent_size == 0 {
+                    0.0
+                } else {
+                    totals[node].size as f64 / parent_size as f64
+                }
+            })
+            .collect();
+        Self { totals, shares }
+    }
+
+    /// Size of an entry, including everything below a directory
+    pub fn size(&self, node: usize) -> u64 {
+        self.totals[node].size
+    }
+
+    /// Fraction of the parent directory's size, from 0 to 1
+    pub fn share(&self, node: usize) -> f64 {
+        self.shares[node]
+    }
+}
+
+/// Bar of `width` columns filled in proportion to `share`, in eighths of a column
+pub fn size_bar(share: f64, width: usize) -> String {
+    let eighths = (share.clamp(0.0, 1.0) * (width * 8) as f64).round() as usize;
+    let mut bar = "█".repeat(eighths / 8);
+    if let Some(partial) = (eighths % 8).checked_sub(1) {
+        bar.push(PARTIAL_BLOCKS[partial]);
+    }
+    let filled = eighths.div_ceil(8);
+    bar.push_str(&" ".repeat(width - filled));
+    bar
+}
+
+/// Bar of `width` columns drawn with `#`, for terminals without block characters
+pub fn ascii_bar(share: f64, width: usize) -> String {
+    let filled = (share.clamp(0.0, 1.0) * width as f64).round() as usize;
+    format!("{}{}", "#".repeat(filled), " ".repeat(width - filled))
+}
+
+/// Share as a percentage with one decimal, e.g. `42.5%`
+pub fn percent(share: f64) -> String {
+    format!("{:.1}%", share * 100.0)
+}
+
+/// Width of a bar given the terminal columns left for it
+pub fn bar_width(available: usize) -> usize {
+    available.clamp(MIN_BAR_WIDTH, MAX_BAR_WIDTH)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_size_bar() {
+        assert_eq!(size_bar(0.0, 4), "    ");
+        assert_eq!(size_bar(1.0, 4), "████");
+        assert_eq!(size_bar(0.5, 4), "██  ");
+        assert_eq!(size_bar(0.3, 4), "█▎  ");
+        assert_eq!(ascii_bar(0.5, 4), "##  ");
+        assert_eq!(percent(0.4251), "42.5%");
+        assert_eq!(bar_width(3), MIN_BAR_WIDTH);
+        assert_eq!(bar_width(25), 25);
+    }
+
+    #[test]
+    fn test_size_shares() {
+        let entries = vec![
+            FileEntry::new(PathBuf::from("root/dir"), true, 4096, None, None),
+            FileEntry::new(PathBuf::from("root/dir/a"), false, 30, None, None),
+            FileEntry::new(PathBuf::from("root/dir/b"), false, 10, None, None),
+            FileEntry::new(PathBuf::from("root/c"), false, 60, None, None),
+        ];
+        let shares = SizeShares::new(&entries);
+        assert_eq!(shares.size(0), 40);
+        assert_eq!(shares.share(0), 0.4);
+        assert_eq!(shares.share(1), 0.75);
+        assert_eq!(shares.share(3), 0.6);
+
+        // Without the directory's contents its own size is kept
+        let shares = SizeShares::new(&[entries[0].clone(), entries[3].clone()]);
+        assert_eq!(shares.size(0), 4096);
+        assert_eq!(shares.share(0), 4096.0 / 4156.0);
+    }
+}
@@ -1,17 +1,87 @@
-use clap::Parser;
+use crate::size_utils::parse_size;
+use clap::{Parser, Subcommand};
 use std::path::PathBuf;
 
 #[derive(Debug, Parser)]
 #[command(version, about, long_about = "The better ls command line.")]
 pub struct CLI {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
     /// The path to the directory to list files from
     #[arg(short, long, value_name = "PATH", value_hint = clap::ValueHint::DirPath)]
     pub path: Option<PathBuf>,
 
-    /// Output format (table, long, tree, json)
+    /// Output format (table, grid, lines, long, tree, json, json-tree, ndjson, csv, tsv, template, html, markdown, markdown-tree, treemap-svg, sunburst-svg)
     #[arg(short, long, value_name = "FORMAT", default_value = "table")]
     pub format: String,
 
+    /// With --format grid, fill rows left to right instead of columns top to bottom
+    #[arg(short = 'x', long)]
+    pub across: bool,
+
+    /// Print one path per line (same as --format lines)
+    #[arg(short = '1')]
+    pub one_per_line: bool,
+
+    /// Print paths separated by NUL bytes instead of newlines, for xargs -0
+    #[arg(short = '0', long)]
+    pub print0: bool,
+
+    /// With --format lines, print absolute paths instead of paths relative to PATH
+    #[arg(long)]
+    pub absolute: bool,
+
+    /// How to quote names with --format lines (literal, shell, shell-escape, c)
+    #[arg(long, value_name = "STYLE", default_value = "literal")]
+    pub quoting_style: String,
+
+    /// Comma-separated columns for table, long, csv and tsv output, e.g. name,size,modified,owner
+    #[arg(long, value_name = "COLUMNS")]
+    pub columns: Option<String>,
+
+    /// Comma-separated columns to append to the default or --columns set
+    #[arg(long, value_name = "COLUMNS")]
+    pub add_columns: Option<String>,
+
+    /// Comma-separated columns to leave out
+    #[arg(long, value_name = "COLUMNS")]
+    pub hide_columns: Option<String>,
+
+    /// With table, long and tree output, show a size bar and the percentage of the
+    /// parent directory's size; directory sizes include everything listed below them
+    /// (with --recursive), otherwise a directory keeps its own size
+    #[arg(long)]
+    pub bars: bool,
+
+    /// With --format json-tree and tree, add the total size and file count below each directory
+    #[arg(long)]
+    pub dir_totals: bool,
+
+    /// With --format tree, show at most N entries per directory and summarize the rest
+    #[arg(long, value_name = "N")]
+    pub tree_collapse: Option<usize>,
+
+    /// With --format tree, list subdirectories before files in each directory
+    #[arg(long)]
+    pub tree_dirs_first: bool,
+
+    /// Characters for tree guide lines and size bars (unicode, ascii)
+    #[arg(long, value_name = "CHARSET", default_value = "unicode")]
+    pub charset: String,
+
+    /// What the shapes of --format treemap-svg and sunburst-svg are colored by (kind, age)
+    #[arg(long, value_name = "COLOR", default_value = "kind")]
+    pub chart_color: String,
+
+    /// Line template for --format template, e.g. '{permissions} {size:>10h} {mtime:%F} {path}'
+    #[arg(long, value_name = "TEMPLATE")]
+    pub template: Option<String>,
+
+    /// Print the JSON Schema of the JSON output formats and exit
+    #[arg(long)]
+    pub json_schema: bool,
+
     /// Show hidden files and directories
     #[arg(short, long)]
     pub all: bool,
@@ -24,30 +94,53 @@ pub struct CLI {
     #[arg(short = 'd', long, value_name = "DEPTH")]
     pub max_depth: Option<usize>,
 
-    /// Sort field (name, size, modified, created, type)
-    #[arg(short, long, value_name = "FIELD", default_value = "name")]
+    /// Sort keys (name, size, modified, created, accessed, type, extension, owner, group,
+    /// permissions, depth, path, inode, name-length, none), comma-separated tie-breakers
+    /// with optional per-key direction, e.g. type,size:desc,name
+    #[arg(short, long, value_name = "KEYS", default_value = "name")]
     pub sort: String,
 
-    /// Sort order (asc, desc)
+    /// Default sort order for keys without a direction (asc, desc)
     #[arg(short, long, value_name = "ORDER", default_value = "asc")]
     pub order: String,
 
+    /// With --recursive, sort all entries globally instead of within each directory
+    /// (tree and long output sort within each directory by default)
+    #[arg(long)]
+    pub flat_sort: bool,
+
+    /// Name collation (natural, version, locale, ascii, ignore-case)
+    #[arg(long, value_name = "COLLATION", default_value = "natural")]
+    pub collation: String,
+
+    /// Group directories before or after files (first, last, none)
+    #[arg(long, value_name = "WHERE", default_value = "none")]
+    pub group_dirs: String,
+
     /// Filter by file extensions (comma-separated)
     #[arg(short, long, value_name = "EXTS", value_delimiter = ',')]
     pub extensions: Option<Vec<String>>,
 
-    /// Minimum file size in bytes
-    #[arg(long, value_name = "SIZE")]
+    /// Minimum file size (bytes, or with a unit such as 10K, 1.5MiB, 500kB)
+    #[arg(long, value_name = "SIZE", value_parser = parse_size)]
     pub min_size: Option<u64>,
 
-    /// Maximum file size in bytes
-    #[arg(long, value_name = "SIZE")]
+    /// Maximum file size (bytes, or with a unit such as 10K, 1.5MiB, 500kB)
+    #[arg(long, value_name = "SIZE", value_parser = parse_size)]
     pub max_size: Option<u64>,
 
-    /// Use human-readable file sizes
+    /// Use human-readable file sizes (powers of 1024: KiB, MiB, ...)
     #[arg(short = 'H', long)]
     pub human_readable: bool,
 
+    /// Use human-readable file sizes in powers of 1000 (kB, MB, ...)
+    #[arg(long)]
+    pub si: bool,
+
+    /// Decimal places for human-readable sizes
+    #[arg(long, value_name = "DIGITS", default_value_t = 1)]
+    pub size_precision: usize,
+
     /// Disable color output
     #[arg(long)]
     pub no_color: bool,
@@ -64,7 +157,96 @@ pub struct CLI {
     #[arg(short, long, value_name = "PATTERN")]
     pub glob: Option<String>,
 
-    /// Show summary statistics
+    /// Show only entries newer than a duration (e.g. 90m, 2d, 3w) or a date (YYYY-MM-DD)
+    #[arg(long, value_name = "TIME")]
+    pub newer: Option<String>,
+
+    /// Show only entries older than a duration (e.g. 90m, 2d, 3w) or a date (YYYY-MM-DD)
+    #[arg(long, value_name = "TIME")]
+    pub older: Option<String>,
+
+    /// Show only entries newer than the given reference file
+    #[arg(long, value_name = "FILE", value_hint = clap::ValueHint::FilePath)]
+    pub newer_than: Option<PathBuf>,
+
+    /// Show only entries older than the given reference file
+    #[arg(long, value_name = "FILE", value_hint = clap::ValueHint::FilePath)]
+    pub older_than: Option<PathBuf>,
+
+    /// Timestamp used by time filters (mtime, ctime, atime, birth)
+    #[arg(long, value_name = "FIELD", default_value = "mtime")]
+    pub time_field: String,
+
+    /// Show only entries owned by this user (name or uid)
+    #[arg(long, value_name = "USER")]
+    pub owner: Option<String>,
+
+    /// Show only entries belonging to this group (name or gid)
+    #[arg(long, value_name = "GROUP")]
+    pub group: Option<String>,
+
+    /// Show only entries whose owner has no user account
+    #[arg(long)]
+    pub nouser: bool,
+
+    /// Show only entries whose group does not exist
+    #[arg(long)]
+    pub nogroup: bool,
+
+    /// Permission test in octal like find -perm: MODE (exact), -MODE (all bits), /MODE (any bit)
+    #[arg(long, value_name = "MODE", allow_hyphen_values = true)]
+    pub perm: Vec<String>,
+
+    /// Show only files with an execute bit set
+    #[arg(long)]
+    pub executable: bool,
+
+    /// Show only world-writable entries
+    #[arg(long)]
+    pub world_writable: bool,
+
+    /// Show only entries with the setuid bit set
+    #[arg(long)]
+    pub setuid: bool,
+
+    /// Show only files whose contents contain this text
+    #[arg(long, value_name = "PATTERN")]
+    pub contains: Option<String>,
+
+    /// Treat the --contains pattern as a regular expression
+    #[arg(long, requires = "contains")]
+    pub regex: bool,
+
+    /// Also search binary files with --contains
+    #[arg(long, requires = "contains")]
+    pub binary: bool,
+
+    /// Show the number of matching lines per file with --contains
+    #[arg(long, requires = "contains")]
+    pub count_matches: bool,
+
+    /// Show only empty files and empty directories
+    #[arg(long, group = "emptiness")]
+    pub empty: bool,
+
+    /// Show only non-empty files and directories
+    #[arg(long, group = "emptiness")]
+    pub non_empty: bool,
+
+    /// With --empty/--non-empty, treat directories containing only hidden entries as empty
+    #[arg(long, requires = "emptiness")]
+    pub empty_ignore_hidden: bool,
+
+    /// Show summary statistics; on stderr for json, json-tree, csv, tsv, lines and template output
     #[arg(long)]
     pub summary: bool,
 }
+
+#[derive(Debug, Clone, Copy, PartialEq, Subcommand)]
+pub enum Command {
+    /// Browse interactively in a full-screen terminal view, using the filter and sort
+    /// options given before `tui`; prints the marked paths, or the directory being
+    /// browsed when nothing is marked, on exit, e.g. `cd "$(ptlist tui)"`
+    #[cfg(feature = "tui")]
+    Tui,
+}
@@ -0,0 +1,341 @@
+use std::cmp::Ordering;
+use unicode_normalization::UnicodeNormalization;
+use unicode_normalization::char::is_combining_mark;
+
+/// How file names are compared when sorting by name
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Collation {
+    /// Characters compared by code point, digit runs compared as numbers
+    #[default]
+    Natural,
+    /// Version ordering with the same semantics as GNU `sort -V` / `ls -v`
+    Version,
+    /// Accent- and case-insensitive ordering following the Unicode default collation
+    /// levels: base letters first, then accents, then case (lowercase first)
+    Locale,
+    /// Plain byte order
+    Ascii,
+    /// Natural ordering with case folded
+    IgnoreCase,
+}
+
+impl Collation {
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s.to_lowercase().as_str() {
+            "natural" => Some(Collation::Natural),
+            "version" => Some(Collation::Version),
+            "locale" => Some(Collation::Locale),
+            "ascii" => Some(Collation::Ascii),
+            "ignore-case" | "ignorecase" => Some(Collation::IgnoreCase),
+            _ => None,
+        }
+    }
+
+    /// Compare two names. Names that collate equally are ordered by their bytes,
+    /// so the result is a total order.
+    pub fn compare(&self, a: &str, b: &str) -> Ordering {
+        let cmp = match self {
+            Collation::Natural => natural_cmp(a, b),
+            Collation::Version => version_cmp(a, b),
+            Collation::Locale => locale_cmp(a, b),
+            Collation::Ascii => Ordering::Equal,
+            Collation::IgnoreCase => {
+                natural_cmp(&a.to_lowercase(), &b.to_lowercase()).then_with(|| natural_cmp(a, b))
+            }
+        };
+        cmp.then_with(|| a.cmp(b))
+    }
+}
+
+/// Natural sort comparison for strings containing numbers.
+///
+/// Digit runs of any length are compared by value; when two names differ only in
+/// leading zeros, the one with fewer zeros sorts first.
+pub fn natural_cmp(a: &str, b: &str) -> Ordering {
+    let mut a_rest = a;
+    let mut b_rest = b;
+    let mut zeros_cmp = Ordering::Equal;
+
+    loop {
+        let (Some(ac), Some(bc)) = (a_rest.chars().next(), b_rest.chars().next()) else {
+            return a_rest
+                .is_empty()
+                .cmp(&b_rest.is_empty())
+                .reverse()
+                .then(zeros_cmp);
+        };
+
+        if ac.is_ascii_digit() && bc.is_ascii_digit() {
+            let (a_num, a_tail) = split_digits(a_rest);
+            let (b_num, b_tail) = split_digits(b_rest);
+            let a_value = a_num.trim_start_matches('0');
+            let b_value = b_num.trim_start_matches('0');
+
+            match a_value
+                .len()
+                .cmp(&b_value.len())
+                .then_with(|| a_value.cmp(b_value))
+            {
+                Ordering::Equal => {
+                    zeros_cmp = zeros_cmp.then(a_num.len().cmp(&b_num.len()));
+                    a_rest = a_tail;
+                    b_rest = b_tail;
+                }
+                other => return other,
+            }
+        } else {
+            match ac.cmp(&bc) {
+                Ordering::Equal => {
+                    a_rest = &a_rest[ac.len_utf8()..];
+                    b_rest = &b_rest[bc.len_utf8()..];
+                }
+                other => return other,
+            }
+        }
+    }
+}
+
+fn split_digits(s: &str) -> (&str, &str) {
+    let end = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+    s.split_at(end)
+}
+
+/// Version comparison, a port of gnulib's `filevercmp` used by `sort -V` and `ls -v`
+pub fn version_cmp(a: &str, b: &str) -> Ordering {
+    let a = a.as_bytes();
+    let b = b.as_bytes();
+
+    // Empty names first, then ".", "..", other hidden names, then the rest
+    match (a.is_empty(), b.is_empty()) {
+        (true, _) | (_, true) => return b.is_empty().cmp(&a.is_empty()),
+        _ => {}
+    }
+    match (a[0] == b'.', b[0] == b'.') {
+        (true, false) => return Ordering::Less,
+        (false, true) => return Ordering::Greater,
+        (true, true) => {
+            for special in [&b"."[..], &b".."[..]] {
+                match (a == special, b == special) {
+                    (true, true) => return Ordering::Equal,
+                    (true, false) => return Ordering::Less,
+                    (false, true) => return Ordering::Greater,
+                    (false, false) => {}
+                }
+            }
+        }
+        (false, false) => {}
+    }
+
+    // Compare without file suffixes first, then with them
+    let a_prefix = file_prefix_len(a);
+    let b_prefix = file_prefix_len(b);
+    let result = verrevcmp(&a[..a_prefix], &b[..b_prefix]);
+    if result != Ordering::Equal || (a_prefix == a.len() && b_prefix == b.len()) {
+        return result;
+    }
+    verrevcmp(a, b)
+}
+
+/// Length of `s` without its longest suffix matching `(\.[A-Za-z~][A-Za-z0-9~]*)*$`.
+/// As in coreutils, a hidden file's leading dot may start the suffix.
+fn file_prefix_len(s: &[u8]) -> usize {
+    let n = s.len();
+    let mut i = 0;
+
+    loop {
+        let prefix_len = i;
+        while i + 1 < n && s[i] == b'.' && (s[i + 1].is_ascii_alphabetic() || s[i + 1] == b'~') {
+            i += 2;
+            while i < n && (s[i].is_ascii_alphanumeric() || s[i] == b'~') {
+                i += 1;
+            }
+        }
+        if i >= n {
+            return prefix_len;
+        }
+        i += 1;
+    }
+}
+
+/// Sort weight of the byte at `pos`: end of string and `~` sort before everything,
+/// letters before other characters
+fn version_order(s: &[u8], pos: usize) -> i32 {
+    match s.get(pos) {
+        None => -1,
+        Some(c) if c.is_ascii_digit() => 0,
+        Some(c) if c.is_ascii_alphabetic() => *c as i32,
+        Some(b'~') => -2,
+        Some(c) => *c as i32 + 256,
+    }
+}
+
+/// Debian-style version comparison of alternating non-digit and digit parts
+fn verrevcmp(a: &[u8], b: &[u8]) -> Ordering {
+    let mut a_pos = 0;
+    let mut b_pos = 0;
+
+    while a_pos < a.len() || b_pos < b.len() {
+        while (a_pos < a.len() && !a[a_pos].is_ascii_digit())
+            || (b_pos < b.len() && !b[b_pos].is_ascii_digit())
+        {
+            let a_order = version_order(a, a_pos);
+            let b_order = version_order(b, b_pos);
+            if a_order != b_order {
+                return a_order.cmp(&b_order);
+            }
+            a_pos += 1;
+            b_pos += 1;
+        }
+
+        while a_pos < a.len() && a[a_pos] == b'0' {
+            a_pos += 1;
+        }
+        while b_pos < b.len() && b[b_pos] == b'0' {
+            b_pos += 1;
+        }
+
+        let mut first_diff = Ordering::Equal;
+        while a_pos < a.len()
+            && b_pos < b.len()
+            && a[a_pos].is_ascii_digit()
+            && b[b_pos].is_ascii_digit()
+        {
+            first_diff = first_diff.then(a[a_pos].cmp(&b[b_pos]));
+            a_pos += 1;
+            b_pos += 1;
+        }
+
+        if a_pos < a.len() && a[a_pos].is_ascii_digit() {
+            return Ordering::Greater;
+        }
+        if b_pos < b.len() && b[b_pos].is_ascii_digit() {
+            return Ordering::Less;
+        }
+        if first_diff != Ordering::Equal {
+            return first_diff;
+        }
+    }
+
+    Ordering::Equal
+}
+
+/// Compare base letters case-insensitively, then accents, then case with lowercase first
+fn locale_cmp(a: &str, b: &str) -> Ordering {
+    let primary = |s: &str| -> String {
+        s.nfd()
+            .filter(|c| !is_combining_mark(*c))
+            .flat_map(char::to_lowercase)
+            .collect()
+    };
+    let secondary = |s: &str| -> String { s.nfd().flat_map(char::to_lowercase).collect() };
+    let tertiary = |s: &str| -> Vec<(bool, char)> {
+        s.nfd()
+            .filter(|c| !is_combining_mark(*c))
+            .map(|c| (c.is_uppercase(), c))
+            .collect()
+    };
+
+    primary(a)
+        .cmp(&primary(b))
+        .then_with(|| secondary(a).cmp(&secondary(b)))
+        .then_with(|| tertiary(a).cmp(&tertiary(b)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sorted(collation: Collation, names: &[&str]) -> Vec<String> {
+        let mut names: Vec<String> = names.iter().map(|s| s.to_string()).collect();
+        names.sort_by(|a, b| collation.compare(a, b));
+        names
+    }
+
+    #[test]
+    fn test_natural_sort() {
+        let mut files = vec!["file10.txt", "file2.txt", "file1.txt", "file20.txt"];
+        files.sort_by(|a, b| natural_cmp(a, b));
+        assert_eq!(
+            files,
+            vec!["file1.txt", "file2.txt", "file10.txt", "file20.txt",]
+        );
+    }
+
+    #[test]
+    fn test_natural_long_numbers_and_zeros() {
+        assert_eq!(
+            natural_cmp("a99999999999999999999999", "a100000000000000000000000"),
+            Ordering::Less
+        );
+        assert_eq!(natural_cmp("file1", "file01"), Ordering::Less);
+        assert_eq!(natural_cmp("file01b", "file1c"), Ordering::Less);
+        assert_eq!(natural_cmp("file007", "file007"), Ordering::Equal);
+    }
+
+    #[test]
+    fn test_ignore_case() {
+        assert_eq!(
+            sorted(Collation::IgnoreCase, &["b", "C", "a", "B"]),
+            vec!["a", "B", "b", "C"]
+        );
+        assert_eq!(
+            sorted(Collation::Natural, &["b", "C", "a"]),
+            vec!["C", "a", "b"]
+        );
+    }
+
+    #[test]
+    fn test_locale() {
+        assert_eq!(
+            sorted(
+                Collation::Locale,
+                &["Zebra", "émile", "apple", "Eve", "eve"]
+            ),
+            vec!["apple", "émile", "eve", "Eve", "Zebra"]
+        );
+    }
+
+    #[test]
+    fn test_version() {
+        // Example from the GNU coreutils manual for `sort -V`
+        assert_eq!(
+            sorted(
+                Collation::Version,
+                &["8.10", "8.5", "8.1", "8.01", "8.010", "8.100", "8.49"]
+            ),
+            vec!["8.01", "8.1", "8.5", "8.010", "8.10", "8.49", "8.100"]
+        );
+        assert_eq!(
+            sorted(
+                Collation::Version,
+                &[
+                    "foo-1.10.tar.gz",
+                    "foo-1.2.tar.gz",
+                    "foo-1.2~rc1.tar.gz",
+                    ".hidden",
+                    "..",
+                    "."
+                ]
+            ),
+            vec![
+                ".",
+                "..",
+                ".hidden",
+                "foo-1.2~rc1.tar.gz",
+                "foo-1.2.tar.gz",
+                "foo-1.10.tar.gz",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_collation_from_str() {
+        assert_eq!(Collation::from_str("version"), Some(Collation::Version));
+        assert_eq!(
+            Collation::from_str("ignore-case"),
+            Some(Collation::IgnoreCase)
+        );
+        assert_eq!(Collation::from_str("klingon"), None);
+    }
+}
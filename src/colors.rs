@@ -1,6 +1,6 @@
-use std::io::Write;
+use std::io;
 use std::path::Path;
-use termcolor::{Color, ColorChoice, ColorSpec, StandardStream, WriteColor};
+use termcolor::{Color, ColorChoice, ColorSpec, WriteColor};
 
 pub struct ColorTheme {
     enabled: bool,
@@ -18,31 +18,46 @@ impl ColorTheme {
         self.enabled
     }
 
-    pub fn print_colored<P: AsRef<Path>>(&self, text: &str, path: P) -> std::io::Result<()> {
-        let mut stdout = StandardStream::stdout(if self.enabled {
+    /// Stream color choice matching the theme
+    pub fn color_choice(&self) -> ColorChoice {
+        if self.enabled {
             ColorChoice::Auto
         } else {
             ColorChoice::Never
-        });
+        }
+    }
 
+    /// Write `text` in the color of `path`
+    pub fn write_colored<W: WriteColor + ?Sized, P: AsRef<Path>>(
+        &self,
+        out: &mut W,
+        text: &str,
+        path: P,
+    ) -> io::Result<()> {
+        if !self.enabled {
+            return write!(out, "{}", text);
+        }
+        out.set_color(&self.color_spec(path))?;
+        write!(out, "{}", text)?;
+        out.reset()
+    }
+
+    /// Color and weight used for a path's name; empty when colors are disabled
+    pub fn color_spec<P: AsRef<Path>>(&self, path: P) -> ColorSpec {
         let path_ref = path.as_ref();
-        let color = self.get_color_for_path(path_ref);
         let mut color_spec = ColorSpec::new();
 
-        if let Some(c) = color {
+        if let Some(c) = self.get_color_for_path(path_ref) {
             color_spec.set_fg(Some(c));
             if self.should_be_bold(path_ref) {
                 color_spec.set_bold(true);
             }
         }
-
-        stdout.set_color(&color_spec)?;
-        write!(&mut stdout, "{}", text)?;
-        stdout.reset()?;
-        Ok(())
+        color_spec
     }
 
-    fn get_color_for_path<P: AsRef<Path>>(&self, path: P) -> Option<Color> {
+    /// Palette color for a path, `None` for uncolored files or when colors are disabled
+    pub fn get_color_for_path<P: AsRef<Path>>(&self, path: P) -> Option<Color> {
         if !self.enabled {
             return None;
         }
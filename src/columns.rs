@@ -0,0 +1,349 @@
+use crate::file_entry::FileEntry;
+use crate::size_utils::SizeFormat;
+use chrono::{DateTime, Local, SecondsFormat};
+
+/// A field of a file entry that can be shown as a column
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Column {
+    Name,
+    Path,
+    /// Absolute path
+    FullPath,
+    Type,
+    Size,
+    /// Bytes allocated on disk
+    DiskUsage,
+    Modified,
+    Created,
+    Accessed,
+    Changed,
+    /// Permission bits in octal, e.g. `0644`
+    Mode,
+    /// Symbolic permissions, e.g. `-rw-r--r--`
+    Permissions,
+    Owner,
+    Group,
+    Uid,
+    Gid,
+    Inode,
+    Links,
+    Extension,
+    /// Nesting level below the listed directory
+    Depth,
+    Matches,
+}
+
+/// Columns exported by `--format csv|tsv` when `--columns` is not given
+pub const DEFAULT_EXPORT_COLUMNS: [Column; 8] = [
+    Column::Name,
+    Column::Path,
+    Column::Type,
+    Column::Size,
+    Column::Modified,
+    Column::Mode,
+    Column::Owner,
+    Column::Group,
+];
+
+/// Columns of `--format table` when `--columns` is not given
+pub const DEFAULT_TABLE_COLUMNS: [Column; 5] = [
+    Column::Name,
+    Column::Type,
+    Column::Size,
+    Column::Modified,
+    Column::Permissions,
+];
+
+/// Columns of `--format long` when `--columns` is not given
+pub const DEFAULT_LONG_COLUMNS: [Column; 6] = [
+    Column::Permissions,
+    Column::Owner,
+    Column::Group,
+    Column::Size,
+    Column::Modified,
+    Column::Name,
+];
+
+impl Column {
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s.to_lowercase().as_str() {
+            "name" => Some(Column::Name),
+            "path" => Some(Column::Path),
+            "full-path" | "fullpath" | "abspath" => Some(Column::FullPath),
+            "type" | "kind" => Some(Column::Type),
+            "size" => Some(Column::Size),
+            "disk-usage" | "du" | "allocated" => Some(Column::DiskUsage),
+            "modified" | "mtime" => Some(Column::Modified),
+            "created" | "birth" | "btime" => Some(Column::Created),
+            "accessed" | "atime" => Some(Column::Accessed),
+            "changed" | "ctime" => Some(Column::Changed),
+            "mode" => Some(Column::Mode),
+            "permissions" | "perms" => Some(Column::Permissions),
+            "owner" | "user" => Some(Column::Owner),
+            "group" => Some(Column::Group),
+            "uid" => Some(Column::Uid),
+            "gid" => Some(Column::Gid),
+            "inode" | "ino" => Some(Column::Inode),
+            "links" | "nlink" => Some(Column::Links),
+            "extension" | "ext" => Some(Column::Extension),
+            "depth" => Some(Column::Depth),
+            "matches" => Some(Column::Matches),
+            _ => None,
+        }
+    }
+
+    /// Parse a comma-separated list such as `name,size,modified`.
+    /// On failure, returns the offending column name.
+    pub fn parse_list(spec: &str) -> Result<Vec<Self>, String> {
+        spec.split(',')
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+            .map(|name| Column::from_str(name).ok_or_else(|| name.to_string()))
+            .collect()
+    }
+
+    /// Work out the columns to show: `columns` replaces the defaults, then
+    /// `add` appends columns not already shown and `hide` removes columns.
+    /// Added columns go before a trailing name column, as in long output.
+    /// On failure, returns the offending column name.
+    pub fn resolve(
+        defaults: &[Column],
+        columns: Option<&str>,
+        add: Option<&str>,
+        hide: Option<&str>,
+    ) -> Result<Vec<Self>, String> {
+        let mut resolved = match columns {
+            Some(spec) => Column::parse_list(spec)?,
+            None => defaults.to_vec(),
+        };
+        if let Some(spec) = add {
+            for column in Column::parse_list(spec)? {
+                if !resolved.contains(&column) {
+                    let at = match resolved.last() {
+                        Some(Column::Name) => resolved.len() - 1,
+                        _ => resolved.len(),
+                    };
+                    resolved.insert(at, column);
+                }
+            }
+        }
+        if let Some(spec) = hide {
+            let hidden = Column::parse_list(spec)?;
+            resolved.retain(|column| !hidden.contains(column));
+        }
+        Ok(resolved)
+    }
+
+    /// Header used for the column in CSV/TSV, the canonical name accepted by `from_str`
+    pub fn header(&self) -> &'static str {
+        match self {
+            Column::Name => "name",
+            Column::Path => "path",
+            Column::FullPath => "full-path",
+            Column::Type => "type",
+            Column::Size => "size",
+            Column::DiskUsage => "disk-usage",
+            Column::Modified => "modified",
+            Column::Created => "created",
+            Column::Accessed => "accessed",
+            Column::Changed => "changed",
+            Column::Mode => "mode",
+            Column::Permissions => "permissions",
+            Column::Owner => "owner",
+            Column::Group => "group",
+            Column::Uid => "uid",
+            Column::Gid => "gid",
+            Column::Inode => "inode",
+            Column::Links => "links",
+            Column::Extension => "extension",
+            Column::Depth => "depth",
+            Column::Matches => "matches",
+        }
+    }
+
+    /// Title used for the column in table and long output
+    pub fn title(&self) -> &'static str {
+        match self {
+            Column::Name => "Name",
+            Column::Path => "Path",
+            Column::FullPath => "Full Path",
+            Column::Type => "Type",
+            Column::Size => "Size",
+            Column::DiskUsage => "Disk Usage",
+            Column::Modified => "Modified",
+            Column::Created => "Created",
+            Column::Accessed => "Accessed",
+            Column::Changed => "Changed",
+            Column::Mode => "Mode",
+            Column::Permissions => "Permissions",
+            Column::Owner => "Owner",
+            Column::Group => "Group",
+            Column::Uid => "Uid",
+            Column::Gid => "Gid",
+            Column::Inode => "Inode",
+            Column::Links => "Links",
+            Column::Extension => "Extension",
+            Column::Depth => "Depth",
+            Column::Matches => "Matches",
+        }
+    }
+
+    /// Whether values are numbers, which are right-aligned
+    pub fn is_numeric(&self) -> bool {
+        matches!(
+            self,
+            Column::Size
+                | Column::DiskUsage
+                | Column::Uid
+                | Column::Gid
+                | Column::Inode
+                | Column::Links
+                | Column::Depth
+                | Column::Matches
+        )
+    }
+
+    /// The value in bytes, for columns that hold a size
+    pub fn bytes(&self, entry: &FileEntry) -> Option<u64> {
+        match self {
+            Column::Size => Some(entry.size()),
+            Column::DiskUsage => Some(entry.disk_usage()),
+            _ => None,
+        }
+    }
+
+    /// The timestamp, for columns that hold one
+    pub fn time(&self, entry: &FileEntry) -> Option<DateTime<Local>> {
+        match self {
+            Column::Modified => Some(entry.modified()),
+            Column::Created => entry.created(),
+            Column::Accessed => entry.accessed(),
+            Column::Changed => entry.changed(),
+            _ => None,
+        }
+    }
+
+    /// Machine-friendly value: sizes in bytes, ISO 8601 timestamps,
+    /// and an empty string for values that are unknown
+    pub fn raw_value(&self, entry: &FileEntry) -> String {
+        if let Some(bytes) = self.bytes(entry) {
+            return bytes.to_string();
+        }
+        match self {
+            Column::Modified | Column::Created | Column::Accessed | Column::Changed => self
+                .time(entry)
+                .map(|t| t.to_rfc3339_opts(SecondsFormat::Secs, false))
+                .unwrap_or_default(),
+            Column::Type => if entry.is_dir() { "directory" } else { "file" }.to_string(),
+            _ => self.display_value(entry, None),
+        }
+    }
+
+    /// Value as shown in table and long output, with human-readable sizes
+    /// when a size format is given
+    pub fn display_value(&self, entry: &FileEntry, size_format: Option<SizeFormat>) -> String {
+        if let Some(bytes) = self.bytes(entry) {
+            return match size_format {
+                Some(size_format) => size_format.format(bytes),
+                None => bytes.to_string(),
+            };
+        }
+        match self {
+            Column::Name => entry.name().to_string(),
+            Column::Path => entry.path().to_string_lossy().into_owned(),
+            Column::FullPath => std::path::absolute(entry.path())
+                .unwrap_or_else(|_| entry.path().to_path_buf())
+                .to_string_lossy()
+                .into_owned(),
+            Column::Type => if entry.is_dir() { "Dir" } else { "File" }.to_string(),
+            Column::Modified | Column::Created | Column::Accessed | Column::Changed => self
+                .time(entry)
+                .map(|t| t.format("%Y-%m-%d %H:%M:%S").to_string())
+                .unwrap_or_default(),
+            Column::Mode => format!("{:04o}", entry.mode() & 0o7777),
+            Column::Permissions => entry.permissions(),
+            Column::Owner => entry.owner().unwrap_or_default().to_string(),
+            Column::Group => entry.group().unwrap_or_default().to_string(),
+            Column::Uid => entry.uid().to_string(),
+            Column::Gid => entry.gid().to_string(),
+            Column::Inode => entry.inode().to_string(),
+            Column::Links => entry.links().to_string(),
+            Column::Extension => entry.extension().unwrap_or_default().to_string(),
+            Column::Depth => entry.depth().to_string(),
+            Column::Matches => entry
+                .match_count()
+                .map(|n| n.to_string())
+                .unwrap_or_default(),
+            Column::Size | Column::DiskUsage => unreachable!("sizes are handled above"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_parse_list() {
+        assert_eq!(
+            Column::parse_list("name, size,mtime"),
+            Ok(vec![Column::Name, Column::Size, Column::Modified])
+        );
+        assert_eq!(Column::parse_list("name,colour"), Err("colour".to_string()));
+    }
+
+    #[test]
+    fn test_resolve() {
+        let defaults = [Column::Name, Column::Size];
+        assert_eq!(
+            Column::resolve(&defaults, None, Some("inode,name"), Some("size")),
+            Ok(vec![Column::Name, Column::Inode])
+        );
+        assert_eq!(
+            Column::resolve(&[Column::Size, Column::Name], None, Some("inode"), None),
+            Ok(vec![Column::Size, Column::Inode, Column::Name])
+        );
+        assert_eq!(
+            Column::resolve(&defaults, Some("links,du"), None, None),
+            Ok(vec![Column::Links, Column::DiskUsage])
+        );
+        assert_eq!(
+            Column::resolve(&defaults, None, None, Some("nope")),
+            Err("nope".to_string())
+        );
+    }
+
+    #[test]
+    fn test_raw_value() {
+        let entry = FileEntry::new(PathBuf::from("dir/report.csv"), false, 2048, None, None)
+            .with_mode(0o100644);
+        assert_eq!(Column::Size.raw_value(&entry), "2048");
+        assert_eq!(Column::Mode.raw_value(&entry), "0644");
+        assert_eq!(Column::Type.raw_value(&entry), "file");
+        assert_eq!(Column::Extension.raw_value(&entry), "csv");
+        assert_eq!(Column::Created.raw_value(&entry), "");
+        assert!(
+            DateTime::parse_from_rfc3339(&Column::Modified.raw_value(&entry)).is_ok(),
+            "modified should be an ISO 8601 timestamp"
+        );
+    }
+
+    #[test]
+    fn test_display_value() {
+        let entry =
+            FileEntry::new(PathBuf::from("dir/report.csv"), false, 2048, None, None).with_depth(1);
+        assert_eq!(
+            Column::Size.display_value(&entry, Some(SizeFormat::default())),
+            "2.0 KiB"
+        );
+        assert_eq!(Column::Size.display_value(&entry, None), "2048");
+        assert_eq!(Column::Type.display_value(&entry, None), "File");
+        assert_eq!(Column::Depth.display_value(&entry, None), "1");
+        assert!(
+            PathBuf::from(Column::FullPath.display_value(&entry, None)).is_absolute(),
+            "full path should be absolute"
+        );
+    }
+}
@@ -1,42 +1,54 @@
 use chrono::{DateTime, Local};
-use serde::Serialize;
 use std::fs::Metadata;
 use std::os::unix::fs::MetadataExt;
 use std::path::{Path, PathBuf};
 
-#[derive(Debug, Clone, Serialize)]
+/// Which timestamp of a file to look at
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TimeField {
+    /// Last content modification (mtime)
+    Modified,
+    /// Last status change (ctime)
+    Changed,
+    /// Last access (atime)
+    Accessed,
+    /// Creation time (birth time), not available on every filesystem
+    Created,
+}
+
+impl TimeField {
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s.to_lowercase().as_str() {
+            "modified" | "mtime" => Some(TimeField::Modified),
+            "changed" | "ctime" => Some(TimeField::Changed),
+            "accessed" | "atime" => Some(TimeField::Accessed),
+            "created" | "birth" | "btime" => Some(TimeField::Created),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
 pub struct FileEntry {
     name: String,
     path: PathBuf,
     is_dir: bool,
     size: u64,
-    #[serde(serialize_with = "serialize_datetime")]
     modified: DateTime<Local>,
-    #[serde(serialize_with = "serialize_datetime_option")]
     created: Option<DateTime<Local>>,
+    accessed: Option<DateTime<Local>>,
+    changed: Option<DateTime<Local>>,
     permissions: u32,
+    uid: u32,
+    gid: u32,
+    inode: u64,
+    links: u64,
+    blocks: u64,
+    depth: usize,
     owner: Option<String>,
     group: Option<String>,
-}
-
-fn serialize_datetime<S>(dt: &DateTime<Local>, serializer: S) -> Result<S::Ok, S::Error>
-where
-    S: serde::Serializer,
-{
-    serializer.serialize_str(&dt.format("%Y-%m-%d %H:%M:%S").to_string())
-}
-
-fn serialize_datetime_option<S>(
-    dt: &Option<DateTime<Local>>,
-    serializer: S,
-) -> Result<S::Ok, S::Error>
-where
-    S: serde::Serializer,
-{
-    match dt {
-        Some(dt) => serialize_datetime(dt, serializer),
-        None => serializer.serialize_none(),
-    }
+    match_count: Option<usize>,
 }
 
 impl FileEntry {
@@ -57,11 +69,20 @@ impl FileEntry {
             path,
             is_dir,
             size,
-            modified: modified.unwrap_or_else(|| Local::now()),
+            modified: modified.unwrap_or_else(Local::now),
             created,
+            accessed: None,
+            changed: None,
             permissions: 0,
+            uid: 0,
+            gid: 0,
+            inode: 0,
+            links: 1,
+            blocks: 0,
+            depth: 0,
             owner: None,
             group: None,
+            match_count: None,
         }
     }
 
@@ -71,13 +92,21 @@ impl FileEntry {
         let modified = metadata
             .modified()
             .ok()
-            .map(|t| DateTime::from(t))
-            .unwrap_or_else(|| Local::now());
-        let created = metadata.created().ok().map(|t| DateTime::from(t));
+            .map(DateTime::from)
+            .unwrap_or_else(Local::now);
+        let created = metadata.created().ok().map(DateTime::from);
         let permissions = metadata.mode();
 
         let mut entry = Self::new(path, is_dir, size, Some(modified), created);
         entry.permissions = permissions;
+        entry.uid = metadata.uid();
+        entry.gid = metadata.gid();
+        entry.inode = metadata.ino();
+        entry.links = metadata.nlink();
+        entry.blocks = metadata.blocks();
+        entry.accessed = metadata.accessed().ok().map(DateTime::from);
+        entry.changed = DateTime::from_timestamp(metadata.ctime(), metadata.ctime_nsec() as u32)
+            .map(|t| t.with_timezone(&Local));
 
         // Try to get owner and group names
         #[cfg(unix)]
@@ -94,6 +123,24 @@ impl FileEntry {
         entry
     }
 
+    /// Set the raw mode bits, as returned by `st_mode`
+    pub fn with_mode(mut self, mode: u32) -> Self {
+        self.permissions = mode;
+        self
+    }
+
+    /// Set the nesting level below the listed directory
+    pub fn with_depth(mut self, depth: usize) -> Self {
+        self.depth = depth;
+        self
+    }
+
+    /// Attach the number of content lines matched by `--contains`
+    pub fn with_match_count(mut self, count: usize) -> Self {
+        self.match_count = Some(count);
+        self
+    }
+
     pub fn name(&self) -> &str {
         &self.name
     }
@@ -106,6 +153,22 @@ impl FileEntry {
         self.is_dir
     }
 
+    /// File type from the mode bits: `file`, `directory`, `symlink`, `fifo`, `socket`,
+    /// `char-device` or `block-device`
+    pub fn kind(&self) -> &'static str {
+        match self.permissions & 0o170000 {
+            0o040000 => "directory",
+            0o120000 => "symlink",
+            0o010000 => "fifo",
+            0o140000 => "socket",
+            0o020000 => "char-device",
+            0o060000 => "block-device",
+            0o100000 => "file",
+            _ if self.is_dir => "directory",
+            _ => "file",
+        }
+    }
+
     pub fn size(&self) -> u64 {
         self.size
     }
@@ -118,6 +181,24 @@ impl FileEntry {
         self.created
     }
 
+    pub fn accessed(&self) -> Option<DateTime<Local>> {
+        self.accessed
+    }
+
+    pub fn changed(&self) -> Option<DateTime<Local>> {
+        self.changed
+    }
+
+    /// Get the timestamp selected by `field`, if the platform provides it
+    pub fn time(&self, field: TimeField) -> Option<DateTime<Local>> {
+        match field {
+            TimeField::Modified => Some(self.modified),
+            TimeField::Changed => self.changed,
+            TimeField::Accessed => self.accessed,
+            TimeField::Created => self.created,
+        }
+    }
+
     pub fn extension(&self) -> Option<&str> {
         self.path.extension().and_then(|s| s.to_str())
     }
@@ -180,6 +261,38 @@ impl FileEntry {
         perms
     }
 
+    /// Raw mode bits including the file type
+    pub fn mode(&self) -> u32 {
+        self.permissions
+    }
+
+    pub fn uid(&self) -> u32 {
+        self.uid
+    }
+
+    pub fn gid(&self) -> u32 {
+        self.gid
+    }
+
+    pub fn inode(&self) -> u64 {
+        self.inode
+    }
+
+    /// Number of hard links
+    pub fn links(&self) -> u64 {
+        self.links
+    }
+
+    /// Bytes allocated on disk, which differs from the size for sparse or small files
+    pub fn disk_usage(&self) -> u64 {
+        self.blocks * 512
+    }
+
+    /// Nesting level below the listed directory, 0 for its direct children
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
     pub fn owner(&self) -> Option<&str> {
         self.owner.as_deref()
     }
@@ -187,4 +300,8 @@ impl FileEntry {
     pub fn group(&self) -> Option<&str> {
         self.group.as_deref()
     }
+
+    pub fn match_count(&self) -> Option<usize> {
+        self.match_count
+    }
 }
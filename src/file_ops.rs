@@ -1,6 +1,25 @@
 use crate::file_entry::FileEntry;
+use serde::Serialize;
 use std::fs;
-use std::path::Path;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// A directory or entry that could not be read during a walk
+#[derive(Debug, Clone, Serialize)]
+pub struct WalkError {
+    #[serde(serialize_with = "crate::schema::serialize_path_lossy")]
+    pub path: PathBuf,
+    pub error: String,
+}
+
+impl WalkError {
+    pub fn new(path: &Path, error: io::Error) -> Self {
+        Self {
+            path: path.to_path_buf(),
+            error: error.to_string(),
+        }
+    }
+}
 
 /// Get the files in the given path.
 /// # Arguments
@@ -8,42 +27,98 @@ use std::path::Path;
 /// # Returns
 /// A vector of FileEntry containing the information about files in the directory.
 pub fn get_files(path: &Path) -> Vec<FileEntry> {
-    let mut entries = Vec::new();
-
-    if let Ok(read_dir) = fs::read_dir(path) {
-        for entry_result in read_dir {
-            if let Ok(entry) = entry_result {
-                if let Ok(metadata) = entry.metadata() {
-                    entries.push(FileEntry::from_metadata(entry.path(), &metadata));
-                }
-            }
-        }
-    }
-
-    entries
+    get_files_recursive(path, 0)
 }
 
 /// Get files recursively from a directory up to a maximum depth
 pub fn get_files_recursive(path: &Path, max_depth: usize) -> Vec<FileEntry> {
-    fn recurse(path: &Path, max_depth: usize, current_depth: usize) -> Vec<FileEntry> {
+    let mut entries = Vec::new();
+    walk(
+        path,
+        max_depth,
+        &|_| {},
+        &mut |e| {
+            entries.push(e);
+            Ok(())
+        },
+        &mut |_, _| {},
+    )
+    .expect("collecting entries cannot fail");
+    entries
+}
+
+/// Walk a directory up to a maximum depth, handing each entry to `on_entry` as soon as
+/// its directory has been read.
+///
+/// Entries of each directory are ordered by `sort_dir` and visited before the contents
+/// of its subdirectories, which are walked in that same order. Anything that cannot be
+/// read is reported to `on_error` and skipped. The walk stops at the first error
+/// returned by `on_entry`, such as a closed output pipe, and returns it.
+pub fn walk(
+    path: &Path,
+    max_depth: usize,
+    sort_dir: &dyn Fn(&mut [FileEntry]),
+    on_entry: &mut dyn FnMut(FileEntry) -> io::Result<()>,
+    on_error: &mut dyn FnMut(&Path, io::Error),
+) -> io::Result<()> {
+    fn recurse(
+        path: &Path,
+        max_depth: usize,
+        current_depth: usize,
+        sort_dir: &dyn Fn(&mut [FileEntry]),
+        on_entry: &mut dyn FnMut(FileEntry) -> io::Result<()>,
+        on_error: &mut dyn FnMut(&Path, io::Error),
+    ) -> io::Result<()> {
         if current_depth > max_depth {
-            return Vec::new();
+            return Ok(());
         }
 
-        let mut entries = get_files(path);
-        let mut subdirs = Vec::new();
+        let read_dir = match fs::read_dir(path) {
+            Ok(read_dir) => read_dir,
+            Err(e) => {
+                on_error(path, e);
+                return Ok(());
+            }
+        };
 
-        for entry in &entries {
-            if entry.is_dir() {
-                let subpath = path.join(entry.name());
-                let mut subentries = recurse(&subpath, max_depth, current_depth + 1);
-                subdirs.append(&mut subentries);
+        let mut entries = Vec::new();
+        for entry in read_dir {
+            let entry = match entry {
+                Ok(entry) => entry,
+                Err(e) => {
+                    on_error(path, e);
+                    continue;
+                }
+            };
+            match entry.metadata() {
+                Ok(metadata) => entries.push(
+                    FileEntry::from_metadata(entry.path(), &metadata).with_depth(current_depth),
+                ),
+                Err(e) => on_error(&entry.path(), e),
             }
         }
+        sort_dir(&mut entries);
 
-        entries.append(&mut subdirs);
-        entries
+        let subdirs: Vec<PathBuf> = entries
+            .iter()
+            .filter(|e| e.is_dir())
+            .map(|e| e.path().to_path_buf())
+            .collect();
+        for entry in entries {
+            on_entry(entry)?;
+        }
+        for subdir in subdirs {
+            recurse(
+                &subdir,
+                max_depth,
+                current_depth + 1,
+                sort_dir,
+                on_entry,
+                on_error,
+            )?;
+        }
+        Ok(())
     }
 
-    recurse(path, max_depth, 0)
+    recurse(path, max_depth, 0, sort_dir, on_entry, on_error)
 }
@@ -1,7 +1,53 @@
-use crate::file_entry::FileEntry;
+use crate::file_entry::{FileEntry, TimeField};
+use chrono::{DateTime, Local};
 use glob::Pattern;
+use regex::bytes::Regex;
+use std::fs::{self, OpenOptions};
+use std::io::{BufRead, BufReader, Cursor, Read};
+use std::os::unix::fs::OpenOptionsExt;
 use std::path::Path;
 
+/// Number of leading bytes inspected when deciding whether a file is binary
+const BINARY_SNIFF_LEN: usize = 8192;
+
+/// Permission test with `find -perm` semantics
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PermMatch {
+    /// `MODE`: permission bits are exactly `MODE`
+    Exact(u32),
+    /// `-MODE`: all bits of `MODE` are set
+    All(u32),
+    /// `/MODE`: any bit of `MODE` is set (or `MODE` is 0)
+    Any(u32),
+}
+
+impl PermMatch {
+    /// Parse an octal mode optionally prefixed with `-` or `/`, e.g. `644`, `-4000`, `/111`
+    pub fn parse(s: &str) -> Result<Self, String> {
+        let (ctor, digits): (fn(u32) -> Self, &str) = if let Some(rest) = s.strip_prefix('-') {
+            (PermMatch::All, rest)
+        } else if let Some(rest) = s.strip_prefix('/') {
+            (PermMatch::Any, rest)
+        } else {
+            (PermMatch::Exact, s)
+        };
+
+        match u32::from_str_radix(digits, 8) {
+            Ok(mode) if !digits.is_empty() && mode <= 0o7777 => Ok(ctor(mode)),
+            _ => Err(format!("invalid permission mode: {}", s)),
+        }
+    }
+
+    pub fn matches(&self, mode: u32) -> bool {
+        let mode = mode & 0o7777;
+        match *self {
+            PermMatch::Exact(bits) => mode == bits,
+            PermMatch::All(bits) => mode & bits == bits,
+            PermMatch::Any(bits) => bits == 0 || mode & bits != 0,
+        }
+    }
+}
+
 pub struct FileFilter {
     extensions: Option<Vec<String>>,
     min_size: Option<u64>,
@@ -10,6 +56,20 @@ pub struct FileFilter {
     files_only: bool,
     glob_pattern: Option<Pattern>,
     show_hidden: bool,
+    time_field: TimeField,
+    newer_than: Option<DateTime<Local>>,
+    older_than: Option<DateTime<Local>>,
+    uid: Option<u32>,
+    gid: Option<u32>,
+    no_user: bool,
+    no_group: bool,
+    perms: Vec<PermMatch>,
+    executable: bool,
+    content_pattern: Option<Regex>,
+    search_binary: bool,
+    count_matches: bool,
+    empty: Option<bool>,
+    empty_ignores_hidden: bool,
 }
 
 impl FileFilter {
@@ -22,6 +82,20 @@ impl FileFilter {
             files_only: false,
             glob_pattern: None,
             show_hidden: false,
+            time_field: TimeField::Modified,
+            newer_than: None,
+            older_than: None,
+            uid: None,
+            gid: None,
+            no_user: false,
+            no_group: false,
+            perms: Vec::new(),
+            executable: false,
+            content_pattern: None,
+            search_binary: false,
+            count_matches: false,
+            empty: None,
+            empty_ignores_hidden: false,
         }
     }
 
@@ -58,7 +132,189 @@ impl FileFilter {
         self
     }
 
+    /// Select which timestamp `newer_than`/`older_than` compare against
+    pub fn with_time_field(mut self, field: TimeField) -> Self {
+        self.time_field = field;
+        self
+    }
+
+    /// Keep only entries whose selected timestamp is strictly after `time`
+    pub fn newer_than(mut self, time: DateTime<Local>) -> Self {
+        self.newer_than = Some(time);
+        self
+    }
+
+    /// Keep only entries whose selected timestamp is strictly before `time`
+    pub fn older_than(mut self, time: DateTime<Local>) -> Self {
+        self.older_than = Some(time);
+        self
+    }
+
+    /// Keep only entries owned by the given user name or numeric uid
+    pub fn with_owner(mut self, owner: &str) -> Result<Self, String> {
+        let uid = match owner.parse() {
+            Ok(uid) => uid,
+            Err(_) => users::get_user_by_name(owner)
+                .map(|u| u.uid())
+                .ok_or_else(|| format!("unknown user: {}", owner))?,
+        };
+        self.uid = Some(uid);
+        Ok(self)
+    }
+
+    /// Keep only entries belonging to the given group name or numeric gid
+    pub fn with_group(mut self, group: &str) -> Result<Self, String> {
+        let gid = match group.parse() {
+            Ok(gid) => gid,
+            Err(_) => users::get_group_by_name(group)
+                .map(|g| g.gid())
+                .ok_or_else(|| format!("unknown group: {}", group))?,
+        };
+        self.gid = Some(gid);
+        Ok(self)
+    }
+
+    /// Keep only entries whose uid has no matching user
+    pub fn no_user(mut self) -> Self {
+        self.no_user = true;
+        self
+    }
+
+    /// Keep only entries whose gid has no matching group
+    pub fn no_group(mut self) -> Self {
+        self.no_group = true;
+        self
+    }
+
+    /// Add a permission test; all added tests must match
+    pub fn with_perm(mut self, perm: PermMatch) -> Self {
+        self.perms.push(perm);
+        self
+    }
+
+    /// Keep only files with any execute bit set
+    pub fn executable(mut self) -> Self {
+        self.executable = true;
+        self
+    }
+
+    /// Keep only entries writable by others
+    pub fn world_writable(self) -> Self {
+        self.with_perm(PermMatch::All(0o002))
+    }
+
+    /// Keep only entries with the setuid bit set
+    pub fn setuid(self) -> Self {
+        self.with_perm(PermMatch::All(0o4000))
+    }
+
+    /// Keep only files whose contents contain `pattern`, as a literal or a regex
+    pub fn with_contents(mut self, pattern: &str, is_regex: bool) -> Result<Self, regex::Error> {
+        let pattern = if is_regex {
+            Regex::new(pattern)?
+        } else {
+            Regex::new(&regex::escape(pattern))?
+        };
+        self.content_pattern = Some(pattern);
+        Ok(self)
+    }
+
+    /// Also search files that look binary (contain a NUL byte)
+    pub fn search_binary(mut self, search: bool) -> Self {
+        self.search_binary = search;
+        self
+    }
+
+    /// Record the number of matching lines on each entry kept by `filter_entries`
+    pub fn count_matches(mut self, count: bool) -> Self {
+        self.count_matches = count;
+        self
+    }
+
+    /// Keep only empty files (size 0) and empty directories
+    pub fn only_empty(mut self) -> Self {
+        self.empty = Some(true);
+        self
+    }
+
+    /// Keep only non-empty files and directories
+    pub fn only_non_empty(mut self) -> Self {
+        self.empty = Some(false);
+        self
+    }
+
+    /// Treat directories that contain only hidden entries as empty
+    pub fn empty_ignores_hidden(mut self, ignore: bool) -> Self {
+        self.empty_ignores_hidden = ignore;
+        self
+    }
+
     pub fn matches(&self, entry: &FileEntry) -> bool {
+        self.matches_metadata(entry) && self.content_match_count(entry) != Some(0)
+    }
+
+    /// Keep `entry` if it matches, attaching its match count when counting is enabled
+    pub fn apply(&self, entry: FileEntry) -> Option<FileEntry> {
+        if !self.matches_metadata(&entry) {
+            return None;
+        }
+        match self.content_match_count(&entry) {
+            None => Some(entry),
+            Some(0) => None,
+            Some(count) if self.count_matches => Some(entry.with_match_count(count)),
+            Some(_) => Some(entry),
+        }
+    }
+
+    /// Count the lines of `entry` matching the content pattern.
+    ///
+    /// Returns `None` when no content pattern is set, and `Some(0)` for anything but
+    /// regular files, unreadable files and binary files that are not searched.
+    /// FIFOs and devices are never read, since they may block or never end.
+    pub fn content_match_count(&self, entry: &FileEntry) -> Option<usize> {
+        let pattern = self.content_pattern.as_ref()?;
+        if entry.is_dir() {
+            return Some(0);
+        }
+
+        // Opening a FIFO without O_NONBLOCK waits for a writer; the type is checked
+        // on the opened file so it cannot be swapped in between
+        let Ok(mut file) = OpenOptions::new()
+            .read(true)
+            .custom_flags(libc::O_NONBLOCK)
+            .open(entry.path())
+        else {
+            return Some(0);
+        };
+        if !file.metadata().is_ok_and(|m| m.file_type().is_file()) {
+            return Some(0);
+        }
+
+        let mut head = Vec::with_capacity(BINARY_SNIFF_LEN);
+        if (&mut file)
+            .take(BINARY_SNIFF_LEN as u64)
+            .read_to_end(&mut head)
+            .is_err()
+        {
+            return Some(0);
+        }
+        if !self.search_binary && head.contains(&0) {
+            return Some(0);
+        }
+
+        let mut count = 0;
+        for line in BufReader::new(Cursor::new(head).chain(file)).split(b'\n') {
+            match line {
+                Ok(line) if pattern.is_match(&line) => count += 1,
+                Ok(_) => {}
+                Err(_) => return Some(0),
+            }
+        }
+        Some(count)
+    }
+
+    /// Check every predicate that only needs the entry's metadata
+    fn matches_metadata(&self, entry: &FileEntry) -> bool {
         // Hidden files
         if !self.show_hidden && is_hidden(entry.path()) {
             return false;
@@ -73,39 +329,98 @@ impl FileFilter {
         }
 
         // Extension filtering
-        if let Some(ref exts) = self.extensions {
-            if !entry.is_dir() {
-                if let Some(ext) = entry.extension() {
-                    if !exts.contains(&ext.to_lowercase()) {
-                        return false;
-                    }
-                } else {
-                    return false;
-                }
+        if let Some(ref exts) = self.extensions
+            && !entry.is_dir()
+        {
+            match entry.extension() {
+                Some(ext) if exts.contains(&ext.to_lowercase()) => {}
+                _ => return false,
             }
         }
 
         // Size filtering
-        if let Some(min) = self.min_size {
-            if entry.size() < min {
-                return false;
-            }
+        if let Some(min) = self.min_size
+            && entry.size() < min
+        {
+            return false;
         }
-        if let Some(max) = self.max_size {
-            if entry.size() > max {
-                return false;
-            }
+        if let Some(max) = self.max_size
+            && entry.size() > max
+        {
+            return false;
         }
 
         // Glob pattern matching
-        if let Some(ref pattern) = self.glob_pattern {
-            if !pattern.matches(entry.name()) {
+        if let Some(ref pattern) = self.glob_pattern
+            && !pattern.matches(entry.name())
+        {
+            return false;
+        }
+
+        // Time filtering; entries without the selected timestamp never match
+        if self.newer_than.is_some() || self.older_than.is_some() {
+            let Some(time) = entry.time(self.time_field) else {
+                return false;
+            };
+            if let Some(newer) = self.newer_than
+                && time <= newer
+            {
+                return false;
+            }
+            if let Some(older) = self.older_than
+                && time >= older
+            {
                 return false;
             }
         }
 
+        // Ownership filtering
+        if self.uid.is_some_and(|uid| entry.uid() != uid) {
+            return false;
+        }
+        if self.gid.is_some_and(|gid| entry.gid() != gid) {
+            return false;
+        }
+        if self.no_user && entry.owner().is_some() {
+            return false;
+        }
+        if self.no_group && entry.group().is_some() {
+            return false;
+        }
+
+        // Permission filtering
+        if !self.perms.iter().all(|p| p.matches(entry.mode())) {
+            return false;
+        }
+        if self.executable && (entry.is_dir() || entry.mode() & 0o111 == 0) {
+            return false;
+        }
+
+        // Emptiness filtering
+        if let Some(want_empty) = self.empty
+            && self.is_empty(entry) != want_empty
+        {
+            return false;
+        }
+
         true
     }
+
+    /// Files are empty when their size is 0, directories when they have no entries.
+    /// Unreadable directories are never considered empty.
+    fn is_empty(&self, entry: &FileEntry) -> bool {
+        if !entry.is_dir() {
+            return entry.size() == 0;
+        }
+
+        match fs::read_dir(entry.path()) {
+            Ok(mut read_dir) => !read_dir.any(|child| match child {
+                Ok(child) => !(self.empty_ignores_hidden && is_hidden(child.file_name())),
+                Err(_) => true,
+            }),
+            Err(_) => false,
+        }
+    }
 }
 
 fn is_hidden<P: AsRef<Path>>(path: P) -> bool {
@@ -116,13 +431,24 @@ fn is_hidden<P: AsRef<Path>>(path: P) -> bool {
         .unwrap_or(false)
 }
 
+impl Default for FileFilter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 pub fn filter_entries(entries: Vec<FileEntry>, filter: &FileFilter) -> Vec<FileEntry> {
-    entries.into_iter().filter(|e| filter.matches(e)).collect()
+    entries
+        .into_iter()
+        .filter_map(|e| filter.apply(e))
+        .collect()
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use chrono::Duration;
+    use std::fs::File;
     use std::path::PathBuf;
 
     #[test]
@@ -174,6 +500,210 @@ mod tests {
         assert!(!filter.matches(&non_matching_file));
     }
 
+    #[test]
+    fn test_time_filter() {
+        let now = Local::now();
+        let filter = FileFilter::new()
+            .newer_than(now - Duration::days(7))
+            .older_than(now - Duration::days(1));
+
+        let recent = FileEntry::new(
+            PathBuf::from("recent.txt"),
+            false,
+            100,
+            Some(now - Duration::hours(1)),
+            None,
+        );
+        let last_week = FileEntry::new(
+            PathBuf::from("last_week.txt"),
+            false,
+            100,
+            Some(now - Duration::days(3)),
+            None,
+        );
+        let ancient = FileEntry::new(
+            PathBuf::from("ancient.txt"),
+            false,
+            100,
+            Some(now - Duration::days(30)),
+            None,
+        );
+
+        assert!(!filter.matches(&recent));
+        assert!(filter.matches(&last_week));
+        assert!(!filter.matches(&ancient));
+
+        // Missing timestamps never match a time filter
+        let created_filter = FileFilter::new()
+            .with_time_field(TimeField::Created)
+            .newer_than(now - Duration::days(7));
+        assert!(!created_filter.matches(&recent));
+    }
+
+    #[test]
+    fn test_perm_match() {
+        assert_eq!(PermMatch::parse("644"), Ok(PermMatch::Exact(0o644)));
+        assert_eq!(PermMatch::parse("-4000"), Ok(PermMatch::All(0o4000)));
+        assert_eq!(PermMatch::parse("/111"), Ok(PermMatch::Any(0o111)));
+        assert!(PermMatch::parse("999").is_err());
+        assert!(PermMatch::parse("-").is_err());
+
+        // File type bits are ignored
+        assert!(PermMatch::Exact(0o644).matches(0o100644));
+        assert!(!PermMatch::Exact(0o644).matches(0o100755));
+        assert!(PermMatch::All(0o750).matches(0o755));
+        assert!(!PermMatch::All(0o770).matches(0o755));
+        assert!(PermMatch::Any(0o022).matches(0o664));
+        assert!(!PermMatch::Any(0o022).matches(0o644));
+        assert!(PermMatch::Any(0).matches(0o000));
+    }
+
+    #[test]
+    fn test_permission_filter() {
+        let script =
+            FileEntry::new(PathBuf::from("run.sh"), false, 10, None, None).with_mode(0o100755);
+        let shared =
+            FileEntry::new(PathBuf::from("shared.txt"), false, 10, None, None).with_mode(0o100666);
+        let suid =
+            FileEntry::new(PathBuf::from("passwd"), false, 10, None, None).with_mode(0o104755);
+        let dir = FileEntry::new(PathBuf::from("bin"), true, 0, None, None).with_mode(0o040755);
+
+        let exec_filter = FileFilter::new().executable();
+        assert!(exec_filter.matches(&script));
+        assert!(!exec_filter.matches(&shared));
+        assert!(!exec_filter.matches(&dir));
+
+        let ww_filter = FileFilter::new().world_writable();
+        assert!(ww_filter.matches(&shared));
+        assert!(!ww_filter.matches(&script));
+
+        let suid_filter = FileFilter::new().setuid();
+        assert!(suid_filter.matches(&suid));
+        assert!(!suid_filter.matches(&script));
+    }
+
+    #[test]
+    fn test_owner_filter() {
+        let entry = FileEntry::new(PathBuf::from("owned.txt"), false, 10, None, None);
+
+        assert!(FileFilter::new().with_owner("0").unwrap().matches(&entry));
+        assert!(
+            !FileFilter::new()
+                .with_owner("12345")
+                .unwrap()
+                .matches(&entry)
+        );
+        assert!(FileFilter::new().with_group("0").unwrap().matches(&entry));
+        assert!(FileFilter::new().with_owner("no-such-user-xyz").is_err());
+
+        // Entries built without metadata have no resolved owner or group
+        assert!(FileFilter::new().no_user().matches(&entry));
+        assert!(FileFilter::new().no_group().matches(&entry));
+    }
+
+    #[test]
+    fn test_content_filter() {
+        use std::io::Write;
+        use tempfile::TempDir;
+
+        let temp_dir = TempDir::new().unwrap();
+        let todo_path = temp_dir.path().join("todo.rs");
+        let mut todo = File::create(&todo_path).unwrap();
+        writeln!(todo, "// TODO: one\nfn main() {{}}\n// TODO: two").unwrap();
+        let clean_path = temp_dir.path().join("clean.rs");
+        File::create(&clean_path)
+            .unwrap()
+            .write_all(b"fn main() {}\n")
+            .unwrap();
+        let binary_path = temp_dir.path().join("blob.bin");
+        File::create(&binary_path)
+            .unwrap()
+            .write_all(b"TODO\0\0")
+            .unwrap();
+
+        let entries = vec![
+            FileEntry::new(todo_path, false, 0, None, None),
+            FileEntry::new(clean_path, false, 0, None, None),
+            FileEntry::new(binary_path, false, 0, None, None),
+        ];
+
+        let filter = FileFilter::new()
+            .with_contents("TODO", false)
+            .unwrap()
+            .count_matches(true);
+        let kept = filter_entries(entries.clone(), &filter);
+        assert_eq!(kept.len(), 1);
+        assert_eq!(kept[0].name(), "todo.rs");
+        assert_eq!(kept[0].match_count(), Some(2));
+
+        let filter = FileFilter::new()
+            .with_contents(r"TODO: t\w+", true)
+            .unwrap()
+            .search_binary(true);
+        assert!(filter.matches(&entries[0]));
+        assert!(!filter.matches(&entries[2]));
+
+        let filter = FileFilter::new()
+            .with_contents("TODO", false)
+            .unwrap()
+            .search_binary(true);
+        assert!(filter.matches(&entries[2]));
+        assert!(FileFilter::new().with_contents("(", true).is_err());
+        assert!(FileFilter::new().with_contents("(", false).is_ok());
+    }
+
+    #[test]
+    fn test_content_filter_skips_special_files() {
+        use std::ffi::CString;
+        use std::os::unix::ffi::OsStrExt;
+        use tempfile::TempDir;
+
+        let temp_dir = TempDir::new().unwrap();
+        let fifo_path = temp_dir.path().join("pipe");
+        let c_path = CString::new(fifo_path.as_os_str().as_bytes()).unwrap();
+        // SAFETY: `c_path` is a valid NUL-terminated path
+        assert_eq!(unsafe { libc::mkfifo(c_path.as_ptr(), 0o644) }, 0);
+
+        // Neither blocks waiting for a writer nor reads an endless device
+        let filter = FileFilter::new().with_contents("", false).unwrap();
+        let fifo = FileEntry::new(fifo_path, false, 0, None, None);
+        assert_eq!(filter.content_match_count(&fifo), Some(0));
+        let zero = FileEntry::new("/dev/zero".into(), false, 0, None, None);
+        assert_eq!(filter.content_match_count(&zero), Some(0));
+    }
+
+    #[test]
+    fn test_empty_filter() {
+        use tempfile::TempDir;
+
+        let temp_dir = TempDir::new().unwrap();
+        let empty_dir = temp_dir.path().join("empty");
+        let hidden_only_dir = temp_dir.path().join("hidden_only");
+        fs::create_dir(&empty_dir).unwrap();
+        fs::create_dir(&hidden_only_dir).unwrap();
+        File::create(hidden_only_dir.join(".keep")).unwrap();
+
+        let empty_file = FileEntry::new(PathBuf::from("empty.txt"), false, 0, None, None);
+        let full_file = FileEntry::new(PathBuf::from("full.txt"), false, 10, None, None);
+        let empty_dir = FileEntry::new(empty_dir, true, 0, None, None);
+        let hidden_only_dir = FileEntry::new(hidden_only_dir, true, 0, None, None);
+
+        let filter = FileFilter::new().only_empty();
+        assert!(filter.matches(&empty_file));
+        assert!(!filter.matches(&full_file));
+        assert!(filter.matches(&empty_dir));
+        assert!(!filter.matches(&hidden_only_dir));
+
+        let filter = FileFilter::new().only_empty().empty_ignores_hidden(true);
+        assert!(filter.matches(&hidden_only_dir));
+
+        let filter = FileFilter::new().only_non_empty();
+        assert!(!filter.matches(&empty_file));
+        assert!(filter.matches(&full_file));
+        assert!(!filter.matches(&empty_dir));
+        assert!(filter.matches(&hidden_only_dir));
+    }
+
     #[test]
     fn test_hidden_filter() {
         let filter = FileFilter::new().show_hidden(false);
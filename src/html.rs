@@ -0,0 +1,389 @@
+use crate::columns::Column;
+use crate::file_entry::FileEntry;
+use crate::output::Renderer;
+use crate::size_utils::SizeFormat;
+use crate::tree::{DirTotals, EntryTree};
+use chrono::Local;
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::io::{self, Write};
+use std::path::Path;
+use termcolor::WriteColor;
+
+/// Self-contained HTML report: summary, per-extension breakdown, a sortable and
+/// filterable table and a collapsible tree, with styles and scripts inlined
+pub struct HtmlRenderer<'a> {
+    root: &'a Path,
+    columns: &'a [Column],
+    size_format: SizeFormat,
+}
+
+impl<'a> HtmlRenderer<'a> {
+    pub fn new(root: &'a Path, columns: &'a [Column]) -> Self {
+        Self {
+            root,
+            columns,
+            size_format: SizeFormat::default(),
+        }
+    }
+
+    /// Format used for the human-readable sizes in the report
+    pub fn size_format(mut self, size_format: SizeFormat) -> Self {
+        self.size_format = size_format;
+        self
+    }
+
+    fn write_summary(&self, entries: &[FileEntry], out: &mut dyn Write) -> io::Result<()> {
+        let files = entries.iter().filter(|e| !e.is_dir()).count();
+        let dirs = entries.len() - files;
+        let hidden = entries.iter().filter(|e| e.name().starts_with('.')).count();
+        let total_size: u64 = entries
+            .iter()
+            .filter(|e| !e.is_dir())
+            .map(|e| e.size())
+            .sum();
+
+        writeln!(out, "<section><h2>Summary</h2><dl class=\"summary\">")?;
+        for (label, value) in [
+            ("Files", files.to_string()),
+            ("Directories", dirs.to_string()),
+            ("Hidden", hidden.to_string()),
+            ("Total size", self.size_format.format(total_size)),
+        ] {
+            writeln!(out, "<div><dt>{}</dt><dd>{}</dd></div>", label, value)?;
+        }
+        writeln!(out, "</dl></section>")
+    }
+
+    fn write_extensions(&self, entries: &[FileEntry], out: &mut dyn Write) -> io::Result<()> {
+        let breakdown = extension_breakdown(entries);
+        let total: u64 = breakdown.iter().map(|e| e.size).sum();
+
+        writeln!(out, "<section><h2>Size by extension</h2>")?;
+        writeln!(
+            out,
+            "<table class=\"sortable\"><thead><tr>\
+             <th>Extension</th><th class=\"num\">Files</th><th class=\"num\">Size</th>\
+             <th class=\"num\">Share</th></tr></thead><tbody>"
+        )?;
+        for ext in &breakdown {
+            let share = if total == 0 {
+                0.0
+            } else {
+                ext.size as f64 * 100.0 / total as f64
+            };
+            writeln!(
+                out,
+                "<tr><td>{}</td><td class=\"num\">{}</td>\
+                 <td class=\"num\" data-sort=\"{}\">{}</td>\
+                 <td class=\"num\" data-sort=\"{:.4}\"><span class=\"bar\" style=\"width:{:.1}%\"></span>{:.1}%</td></tr>",
+                ext.extension.map(escape).unwrap_or(Cow::Borrowed("(none)")),
+                ext.files,
+                ext.size,
+                self.size_format.format(ext.size),
+                share,
+                share,
+                share
+            )?;
+        }
+        writeln!(out, "</tbody></table></section>")
+    }
+
+    fn write_table(&self, entries: &[FileEntry], out: &mut dyn Write) -> io::Result<()> {
+        writeln!(out, "<section><h2>Entries</h2>")?;
+        writeln!(
+            out,
+            "<input type=\"search\" class=\"filter\" placeholder=\"Filter entries…\" aria-label=\"Filter entries\">"
+        )?;
+        write!(out, "<table class=\"sortable filterable\"><thead><tr>")?;
+        for column in self.columns {
+            write!(
+                out,
+                "<th{}>{}</th>",
+                numeric_class(*column),
+                escape(column.title())
+            )?;
+        }
+        writeln!(out, "</tr></thead><tbody>")?;
+
+        for entry in entries {
+            write!(
+                out,
+                "<tr{}>",
+                if entry.is_dir() { " class=\"dir\"" } else { "" }
+            )?;
+            for column in self.columns {
+                let shown = column.display_value(entry, Some(self.size_format));
+                let raw = column.raw_value(entry);
+                if raw == shown {
+                    write!(out, "<td{}>{}</td>", numeric_class(*column), escape(&shown))?;
+                } else {
+                    write!(
+                        out,
+                        "<td{} data-sort=\"{}\">{}</td>",
+                        numeric_class(*column),
+                        escape(&raw),
+                        escape(&shown)
+                    )?;
+                }
+            }
+            writeln!(out, "</tr>")?;
+        }
+        writeln!(out, "</tbody></table></section>")
+    }
+
+    fn write_tree(&self, entries: &[FileEntry], out: &mut dyn Write) -> io::Result<()> {
+        let tree = EntryTree::new(entries);
+        let totals = tree.totals(entries);
+
+        writeln!(out, "<section><h2>Tree</h2><ul class=\"tree\">")?;
+        for &node in tree.roots() {
+            self.write_tree_node(entries, &tree, &totals, node, out)?;
+        }
+        writeln!(out, "</ul></section>")
+    }
+
+    fn write_tree_node(
+        &self,
+        entries: &[FileEntry],
+        tree: &EntryTree,
+        totals: &[DirTotals],
+        node: usize,
+        out: &mut dyn Write,
+    ) -> io::Result<()> {
+        let entry = &entries[node];
+        if !entry.is_dir() {
+            return writeln!(
+                out,
+                "<li>{} <span class=\"size\">{}</span></li>",
+                escape(entry.name()),
+                self.size_format.format(entry.size())
+            );
+        }
+
+        let children = tree.children(node);
+        writeln!(
+            out,
+            "<li><details{}><summary class=\"dir\">{}/ <span class=\"size\">{}, {} files</span></summary><ul>",
+            if entry.depth() == 0 { " open" } else { "" },
+            escape(entry.name()),
+            self.size_format.format(totals[node].size),
+            totals[node].files
+        )?;
+        for &child in children {
+            self.write_tree_node(entries, tree, totals, child, out)?;
+        }
+        writeln!(out, "</ul></details></li>")
+    }
+}
+
+impl Renderer for HtmlRenderer<'_> {
+    fn render(&self, entries: &[FileEntry], out: &mut dyn WriteColor) -> io::Result<()> {
+        let title = format!("ptlist: {}", self.root.display());
+        writeln!(out, "<!DOCTYPE html>")?;
+        writeln!(out, "<html lang=\"en\"><head><meta charset=\"utf-8\">")?;
+        writeln!(
+            out,
+            "<meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">"
+        )?;
+        writeln!(out, "<title>{}</title>", escape(&title))?;
+        writeln!(out, "<style>{}</style></head><body>", STYLE)?;
+        writeln!(out, "<h1>{}</h1>", escape(&title))?;
+        writeln!(
+            out,
+            "<p class=\"meta\">Generated by ptlist {} on {}</p>",
+            env!("CARGO_PKG_VERSION"),
+            Local::now().format("%Y-%m-%d %H:%M:%S")
+        )?;
+
+        self.write_summary(entries, out)?;
+        self.write_extensions(entries, out)?;
+        self.write_table(entries, out)?;
+        self.write_tree(entries, out)?;
+
+        writeln!(out, "<script>{}</script></body></html>", SCRIPT)
+    }
+}
+
+/// Number and total size of the files with one extension
+#[derive(Debug, PartialEq)]
+struct ExtensionTotals<'a> {
+    extension: Option<&'a str>,
+    files: usize,
+    size: u64,
+}
+
+/// Files grouped by extension, largest total size first
+fn extension_breakdown(entries: &[FileEntry]) -> Vec<ExtensionTotals<'_>> {
+    let mut groups: HashMap<Option<&str>, ExtensionTotals> = HashMap::new();
+    for entry in entries.iter().filter(|e| !e.is_dir()) {
+        let extension = entry.extension();
+        let group = groups.entry(extension).or_insert(ExtensionTotals {
+            extension,
+            files: 0,
+            size: 0,
+        });
+        group.files += 1;
+        group.size += entry.size();
+    }
+
+    let mut breakdown: Vec<_> = groups.into_values().collect();
+    breakdown.sort_by(|a, b| {
+        b.size
+            .cmp(&a.size)
+            .then_with(|| a.extension.cmp(&b.extension))
+    });
+    breakdown
+}
+
+fn numeric_class(column: Column) -> &'static str {
+    if column.is_numeric() {
+        " class=\"num\""
+    } else {
+        ""
+    }
+}
+
+/// Escape text for use in HTML or SVG content and quoted attribute values
+pub(crate) fn escape(text: &str) -> Cow<'_, str> {
+    if !text.contains(['&', '<', '>', '"', '\'']) {
+        return Cow::Borrowed(text);
+    }
+    let mut escaped = String::with_capacity(text.len() + 8);
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    Cow::Owned(escaped)
+}
+
+const STYLE: &str = r#"
+body { font-family: system-ui, sans-serif; margin: 2rem; color: #222; }
+h1 { font-size: 1.4rem; word-break: break-all; }
+.meta { color: #666; }
+section { margin-top: 2rem; }
+.summary { display: flex; gap: 2rem; }
+.summary dt { color: #666; font-size: .85rem; }
+.summary dd { margin: 0; font-size: 1.2rem; font-weight: 600; }
+table { border-collapse: collapse; }
+th, td { padding: .25rem .75rem; border-bottom: 1px solid #ddd; text-align: left; white-space: nowrap; }
+th { cursor: pointer; user-select: none; background: #f4f4f4; }
+th[aria-sort=ascending]::after { content: " ▲"; }
+th[aria-sort=descending]::after { content: " ▼"; }
+.num { text-align: right; font-variant-numeric: tabular-nums; }
+.dir { color: #1d5fbf; font-weight: 600; }
+.bar { display: inline-block; height: .7em; margin-right: .5em; background: #7a9fd6; }
+.filter { margin-bottom: .5rem; padding: .3rem; width: 20rem; }
+.tree, .tree ul { list-style: none; padding-left: 1.2rem; }
+.tree summary { cursor: pointer; }
+.size { color: #666; font-weight: normal; }
+"#;
+
+const SCRIPT: &str = r#"
+document.querySelectorAll("table.sortable").forEach(function (table) {
+  table.querySelectorAll("th").forEach(function (th, column) {
+    th.addEventListener("click", function () {
+      var ascending = th.getAttribute("aria-sort") !== "ascending";
+      table.querySelectorAll("th").forEach(function (h) { h.removeAttribute("aria-sort"); });
+      th.setAttribute("aria-sort", ascending ? "ascending" : "descending");
+      var numeric = th.classList.contains("num");
+      var key = function (row) {
+        var cell = row.cells[column];
+        var value = cell.hasAttribute("data-sort") ? cell.getAttribute("data-sort") : cell.textContent;
+        return numeric ? parseFloat(value) || 0 : value.toLowerCase();
+      };
+      var body = table.tBodies[0];
+      Array.from(body.rows)
+        .sort(function (a, b) {
+          var x = key(a), y = key(b);
+          return (x < y ? -1 : x > y ? 1 : 0) * (ascending ? 1 : -1);
+        })
+        .forEach(function (row) { body.appendChild(row); });
+    });
+  });
+});
+document.querySelectorAll("input.filter").forEach(function (input) {
+  var table = input.nextElementSibling;
+  input.addEventListener("input", function () {
+    var needle = input.value.toLowerCase();
+    Array.from(table.tBodies[0].rows).forEach(function (row) {
+      row.hidden = needle !== "" && row.textContent.toLowerCase().indexOf(needle) < 0;
+    });
+  });
+});
+"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+    use termcolor::NoColor;
+
+    fn entries() -> Vec<FileEntry> {
+        vec![
+            FileEntry::new(PathBuf::from("root/src"), true, 4096, None, None),
+            FileEntry::new(PathBuf::from("root/src/main.rs"), false, 300, None, None).with_depth(1),
+            FileEntry::new(PathBuf::from("root/src/lib.rs"), false, 200, None, None).with_depth(1),
+            FileEntry::new(PathBuf::from("root/<b>.txt"), false, 1000, None, None),
+            FileEntry::new(PathBuf::from("root/Makefile"), false, 10, None, None),
+        ]
+    }
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(escape("plain"), "plain");
+        assert_eq!(
+            escape("<a href=\"x\">&'"),
+            "&lt;a href=&quot;x&quot;&gt;&amp;&#39;"
+        );
+    }
+
+    #[test]
+    fn test_extension_breakdown() {
+        let entries = entries();
+        let breakdown = extension_breakdown(&entries);
+        assert_eq!(
+            breakdown,
+            vec![
+                ExtensionTotals {
+                    extension: Some("txt"),
+                    files: 1,
+                    size: 1000
+                },
+                ExtensionTotals {
+                    extension: Some("rs"),
+                    files: 2,
+                    size: 500
+                },
+                ExtensionTotals {
+                    extension: None,
+                    files: 1,
+                    size: 10
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_report() {
+        let root = PathBuf::from("root");
+        let columns = [Column::Name, Column::Size];
+        let mut out = NoColor::new(Vec::new());
+        HtmlRenderer::new(&root, &columns)
+            .render(&entries(), &mut out)
+            .unwrap();
+        let html = String::from_utf8(out.into_inner()).unwrap();
+
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains("<td>&lt;b&gt;.txt</td>"));
+        assert!(html.contains("<td class=\"num\" data-sort=\"300\">300 B</td>"));
+        assert!(html.contains("src/ <span class=\"size\">500 B, 2 files</span>"));
+        assert!(!html.contains("<link") && !html.contains("src=\"http"));
+    }
+}
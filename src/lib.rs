@@ -1,17 +1,47 @@
+pub mod bars;
 pub mod cli;
+pub mod collation;
 pub mod colors;
+pub mod columns;
 pub mod file_entry;
 pub mod file_ops;
 pub mod filtering;
+pub mod html;
 pub mod output;
+pub mod quoting;
+pub mod schema;
 pub mod size_utils;
 pub mod sorting;
+pub mod svg;
+pub mod template;
+pub mod terminal;
+pub mod time_utils;
+pub mod tree;
+#[cfg(feature = "tui")]
+pub mod tui;
 
-pub use cli::CLI;
+pub use bars::SizeShares;
+pub use cli::{CLI, Command};
+pub use collation::Collation;
 pub use colors::ColorTheme;
-pub use file_entry::FileEntry;
-pub use file_ops::{get_files, get_files_recursive};
-pub use filtering::{FileFilter, filter_entries};
-pub use output::{print_json, print_long, print_table, print_tree};
-pub use size_utils::HumanSize;
-pub use sorting::{SortField, SortOrder, sort_entries};
+pub use columns::Column;
+pub use file_entry::{FileEntry, TimeField};
+pub use file_ops::{WalkError, get_files, get_files_recursive, walk};
+pub use filtering::{FileFilter, PermMatch, filter_entries};
+pub use html::HtmlRenderer;
+pub use output::{
+    DelimitedRenderer, GridRenderer, JsonRenderer, JsonTreeRenderer, LinesRenderer, ListingSummary,
+    LongRenderer, MarkdownTreeRenderer, NdjsonRenderer, Renderer, TableRenderer, TemplateRenderer,
+    TreeCharset, TreeRenderer, write_ndjson_entry, write_ndjson_summary,
+};
+pub use quoting::QuotingStyle;
+pub use schema::{Envelope, JSON_SCHEMA, SCHEMA_VERSION};
+pub use size_utils::{HumanSize, SizeFormat};
+pub use sorting::{
+    DirGrouping, SortField, SortKey, SortOrder, Sorter, sort_entries, sort_entries_by,
+};
+pub use svg::{Chart, ChartColor, ChartRenderer};
+pub use template::Template;
+pub use tree::{DirTotals, EntryTree};
+#[cfg(feature = "tui")]
+pub use tui::{Browser, Exit};
@@ -1,31 +1,274 @@
+use chrono::{DateTime, Local};
 use clap::Parser;
+use ptlist::columns::{DEFAULT_EXPORT_COLUMNS, DEFAULT_LONG_COLUMNS, DEFAULT_TABLE_COLUMNS};
+use ptlist::time_utils::parse_time_spec;
+#[cfg(feature = "tui")]
+use ptlist::{Browser, Command, Exit};
 use ptlist::{
-    CLI, ColorTheme, FileFilter, HumanSize, SortField, SortOrder, filter_entries, get_files,
-    get_files_recursive, print_json, print_long, print_table, print_tree, sort_entries,
+    CLI, Chart, ChartColor, ChartRenderer, Collation, ColorTheme, Column, DelimitedRenderer,
+    DirGrouping, Envelope, FileEntry, FileFilter, GridRenderer, HtmlRenderer, JSON_SCHEMA,
+    JsonRenderer, JsonTreeRenderer, LinesRenderer, ListingSummary, LongRenderer,
+    MarkdownTreeRenderer, NdjsonRenderer, PermMatch, QuotingStyle, Renderer, SizeFormat, SortKey,
+    SortOrder, Sorter, TableRenderer, Template, TemplateRenderer, TimeField, TreeCharset,
+    TreeRenderer, WalkError, filter_entries, walk, write_ndjson_entry, write_ndjson_summary,
 };
-use std::path::PathBuf;
+use serde::Serialize;
+use std::borrow::Cow;
+#[cfg(feature = "tui")]
+use std::io::IsTerminal;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
 use std::process;
+use termcolor::BufferedStandardStream;
 
-fn print_summary(entries: &[ptlist::FileEntry]) {
+/// The `options` object of JSON output. Its field names are part of the versioned
+/// schema in `schema/ptlist-output-v1.json`, so they are spelled out here instead of
+/// following the names of the command-line struct.
+#[derive(Debug, Serialize)]
+struct ListingOptions<'a> {
+    /// Output format after resolving `-1`/`-0`
+    format: &'a str,
+    all: bool,
+    recursive: bool,
+    max_depth: Option<usize>,
+    sort: &'a str,
+    order: &'a str,
+    flat_sort: bool,
+    collation: &'a str,
+    group_dirs: &'a str,
+    extensions: Option<&'a [String]>,
+    min_size: Option<u64>,
+    max_size: Option<u64>,
+    dirs_only: bool,
+    files_only: bool,
+    glob: Option<&'a str>,
+    newer: Option<&'a str>,
+    older: Option<&'a str>,
+    newer_than: Option<Cow<'a, str>>,
+    older_than: Option<Cow<'a, str>>,
+    time_field: &'a str,
+    owner: Option<&'a str>,
+    group: Option<&'a str>,
+    nouser: bool,
+    nogroup: bool,
+    perm: &'a [String],
+    executable: bool,
+    world_writable: bool,
+    setuid: bool,
+    contains: Option<&'a str>,
+    regex: bool,
+    binary: bool,
+    count_matches: bool,
+    empty: bool,
+    non_empty: bool,
+    empty_ignore_hidden: bool,
+    dir_totals: bool,
+}
+
+impl<'a> ListingOptions<'a> {
+    fn new(cli: &'a CLI, format: &'a str) -> Self {
+        Self {
+            format,
+            all: cli.all,
+            recursive: cli.recursive,
+            max_depth: cli.max_depth,
+            sort: &cli.sort,
+            order: &cli.order,
+            flat_sort: cli.flat_sort,
+            collation: &cli.collation,
+            group_dirs: &cli.group_dirs,
+            extensions: cli.extensions.as_deref(),
+            min_size: cli.min_size,
+            max_size: cli.max_size,
+            dirs_only: cli.dirs_only,
+            files_only: cli.files_only,
+            glob: cli.glob.as_deref(),
+            newer: cli.newer.as_deref(),
+            older: cli.older.as_deref(),
+            newer_than: cli.newer_than.as_deref().map(Path::to_string_lossy),
+            older_than: cli.older_than.as_deref().map(Path::to_string_lossy),
+            time_field: &cli.time_field,
+            owner: cli.owner.as_deref(),
+            group: cli.group.as_deref(),
+            nouser: cli.nouser,
+            nogroup: cli.nogroup,
+            perm: &cli.perm,
+            executable: cli.executable,
+            world_writable: cli.world_writable,
+            setuid: cli.setuid,
+            contains: cli.contains.as_deref(),
+            regex: cli.regex,
+            binary: cli.binary,
+            count_matches: cli.count_matches,
+            empty: cli.empty,
+            non_empty: cli.non_empty,
+            empty_ignore_hidden: cli.empty_ignore_hidden,
+            dir_totals: cli.dir_totals,
+        }
+    }
+}
+
+/// Wrap JSON output in the versioned envelope describing this listing
+fn json_envelope(cli: &CLI, path: &Path, format: &str) -> Envelope {
+    match Envelope::new(path, &ListingOptions::new(cli, format)) {
+        Ok(envelope) => envelope,
+        Err(e) => {
+            eprintln!("Error: Cannot serialize options: {}", e);
+            process::exit(1);
+        }
+    }
+}
+
+fn print_summary(
+    out: &mut dyn Write,
+    entries: &[ptlist::FileEntry],
+    size_format: SizeFormat,
+) -> io::Result<()> {
     let total_files = entries.iter().filter(|e| !e.is_dir()).count();
     let total_dirs = entries.iter().filter(|e| e.is_dir()).count();
     let total_size: u64 = entries.iter().map(|e| e.size()).sum();
     let hidden_count = entries.iter().filter(|e| e.name().starts_with('.')).count();
 
-    println!("\nSummary:");
-    println!("  Files: {}", total_files);
-    println!("  Directories: {}", total_dirs);
-    println!("  Total Size: {}", HumanSize(total_size));
-    println!("\nFile Statistics:");
-    println!("  Files: {}", total_files);
-    println!("  Directories: {}", total_dirs);
-    println!("  Hidden: {}", hidden_count);
-    println!("  Total Size: {}", HumanSize(total_size));
+    writeln!(out, "\nSummary:")?;
+    writeln!(out, "  Files: {}", total_files)?;
+    writeln!(out, "  Directories: {}", total_dirs)?;
+    writeln!(out, "  Total Size: {}", size_format.format(total_size))?;
+    writeln!(out, "\nFile Statistics:")?;
+    writeln!(out, "  Files: {}", total_files)?;
+    writeln!(out, "  Directories: {}", total_dirs)?;
+    writeln!(out, "  Hidden: {}", hidden_count)?;
+    writeln!(out, "  Total Size: {}", size_format.format(total_size))
+}
+
+/// Exit if writing to stdout failed. A reader that stops early, as in
+/// `ptlist | head`, closes the pipe; that is not treated as an error.
+fn finish_output(result: io::Result<()>) {
+    match result {
+        Err(e) if e.kind() != io::ErrorKind::BrokenPipe => {
+            eprintln!("Error: Cannot write output: {}", e);
+            process::exit(1);
+        }
+        _ => {}
+    }
+}
+
+/// Run `ptlist tui` and print the selected paths, one per line.
+/// Leaving with Esc or Ctrl-C prints nothing and exits with status 1.
+#[cfg(feature = "tui")]
+fn run_browser(cli: &CLI, path: &Path, filter: &FileFilter, sorter: Sorter) {
+    if !io::stderr().is_terminal() {
+        eprintln!("Error: ptlist tui needs a terminal");
+        process::exit(1);
+    }
+    // Absolute paths keep the parent directory reachable and work after cd
+    let root = match path.canonicalize() {
+        Ok(root) => root,
+        Err(e) => {
+            eprintln!("Error: Cannot resolve path {:?}: {}", path, e);
+            process::exit(1);
+        }
+    };
+    let color_theme = ColorTheme::new(!cli.no_color);
+    let human_size =
+        (cli.human_readable || cli.si).then_some(SizeFormat::new(cli.si, cli.size_precision));
+
+    match Browser::new(root, filter, sorter, &color_theme)
+        .size_format(human_size)
+        .run()
+    {
+        Ok(Exit::Select(paths)) => {
+            let mut stdout = io::stdout().lock();
+            finish_output(
+                paths
+                    .iter()
+                    .try_for_each(|p| writeln!(stdout, "{}", p.display()))
+                    .and_then(|_| stdout.flush()),
+            );
+        }
+        Ok(Exit::Cancel) => process::exit(1),
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            process::exit(1);
+        }
+    }
+}
+
+/// Columns for table, long, csv and tsv output from --columns, --add-columns and
+/// --hide-columns. Match counts are shown by default when they are computed.
+fn resolve_columns(cli: &CLI, defaults: &[Column]) -> Vec<Column> {
+    let add = match (cli.count_matches && cli.columns.is_none(), &cli.add_columns) {
+        (true, Some(add)) => Some(format!("matches,{}", add)),
+        (true, None) => Some("matches".to_string()),
+        (false, add) => add.clone(),
+    };
+    match Column::resolve(
+        defaults,
+        cli.columns.as_deref(),
+        add.as_deref(),
+        cli.hide_columns.as_deref(),
+    ) {
+        Ok(columns) => columns,
+        Err(column) => {
+            eprintln!("Error: Invalid column: {}", column);
+            process::exit(1);
+        }
+    }
+}
+
+/// Resolve a time threshold from a duration/date argument and/or a reference file.
+/// When both are given, the more restrictive one wins according to `pick`.
+fn resolve_time_threshold(
+    spec: Option<&str>,
+    reference: Option<&Path>,
+    field: TimeField,
+    pick: fn(DateTime<Local>, DateTime<Local>) -> DateTime<Local>,
+) -> Option<DateTime<Local>> {
+    let from_spec = spec.map(|s| match parse_time_spec(s, Local::now()) {
+        Ok(time) => time,
+        Err(e) => {
+            eprintln!("Error: Invalid time: {}", e);
+            process::exit(1);
+        }
+    });
+
+    let from_reference = reference.map(|path| {
+        let entry = match path.metadata() {
+            Ok(metadata) => FileEntry::from_metadata(path.to_path_buf(), &metadata),
+            Err(e) => {
+                eprintln!("Error: Cannot read reference file {:?}: {}", path, e);
+                process::exit(1);
+            }
+        };
+        match entry.time(field) {
+            Some(time) => time,
+            None => {
+                eprintln!(
+                    "Error: Reference file {:?} has no {:?} timestamp",
+                    path, field
+                );
+                process::exit(1);
+            }
+        }
+    });
+
+    match (from_spec, from_reference) {
+        (Some(a), Some(b)) => Some(pick(a, b)),
+        (a, b) => a.or(b),
+    }
 }
 
 fn main() {
     let cli = CLI::parse();
-    let path = cli.path.unwrap_or(PathBuf::from("."));
+    if cli.json_schema {
+        let mut stdout = io::stdout().lock();
+        finish_output(
+            stdout
+                .write_all(JSON_SCHEMA.as_bytes())
+                .and_then(|_| stdout.flush()),
+        );
+        return;
+    }
+    let path = cli.path.clone().unwrap_or(PathBuf::from("."));
 
     // Check if the path exists
     if !path.exists() {
@@ -33,19 +276,12 @@ fn main() {
         process::exit(1);
     }
 
-    // Get files with recursion if specified
-    let mut entries = if cli.recursive {
-        get_files_recursive(&path, cli.max_depth.unwrap_or(std::usize::MAX))
-    } else {
-        get_files(&path)
-    };
-
     // Apply filters
     let filter = FileFilter::new()
         .show_hidden(cli.all)
         .with_size_range(cli.min_size, cli.max_size);
 
-    let filter = if let Some(exts) = cli.extensions {
+    let filter = if let Some(exts) = cli.extensions.clone() {
         filter.with_extensions(exts)
     } else {
         filter
@@ -59,8 +295,8 @@ fn main() {
         filter
     };
 
-    let filter = if let Some(pattern) = cli.glob {
-        match filter.with_glob(&pattern) {
+    let filter = if let Some(pattern) = &cli.glob {
+        match filter.with_glob(pattern) {
             Ok(f) => f,
             Err(e) => {
                 eprintln!("Error: Invalid glob pattern: {}", e);
@@ -71,17 +307,87 @@ fn main() {
         filter
     };
 
-    entries = filter_entries(entries, &filter);
-
-    // Apply sorting
-    let sort_field = match SortField::from_str(&cli.sort) {
+    let time_field = match TimeField::from_str(&cli.time_field) {
         Some(field) => field,
         None => {
-            eprintln!("Error: Invalid sort field: {}", cli.sort);
+            eprintln!("Error: Invalid time field: {}", cli.time_field);
             process::exit(1);
         }
     };
 
+    let mut filter = filter.with_time_field(time_field);
+    if let Some(time) = resolve_time_threshold(
+        cli.newer.as_deref(),
+        cli.newer_than.as_deref(),
+        time_field,
+        DateTime::max,
+    ) {
+        filter = filter.newer_than(time);
+    }
+    if let Some(time) = resolve_time_threshold(
+        cli.older.as_deref(),
+        cli.older_than.as_deref(),
+        time_field,
+        DateTime::min,
+    ) {
+        filter = filter.older_than(time);
+    }
+
+    if let Some(owner) = cli.owner.as_deref() {
+        filter = filter.with_owner(owner).unwrap_or_else(|e| {
+            eprintln!("Error: Invalid owner: {}", e);
+            process::exit(1);
+        });
+    }
+    if let Some(group) = cli.group.as_deref() {
+        filter = filter.with_group(group).unwrap_or_else(|e| {
+            eprintln!("Error: Invalid group: {}", e);
+            process::exit(1);
+        });
+    }
+    if cli.nouser {
+        filter = filter.no_user();
+    }
+    if cli.nogroup {
+        filter = filter.no_group();
+    }
+    for perm in &cli.perm {
+        match PermMatch::parse(perm) {
+            Ok(perm) => filter = filter.with_perm(perm),
+            Err(e) => {
+                eprintln!("Error: Invalid permission: {}", e);
+                process::exit(1);
+            }
+        }
+    }
+    if cli.executable {
+        filter = filter.executable();
+    }
+    if cli.world_writable {
+        filter = filter.world_writable();
+    }
+    if cli.setuid {
+        filter = filter.setuid();
+    }
+
+    if let Some(pattern) = cli.contains.as_deref() {
+        filter = match filter.with_contents(pattern, cli.regex) {
+            Ok(f) => f.search_binary(cli.binary).count_matches(cli.count_matches),
+            Err(e) => {
+                eprintln!("Error: Invalid content pattern: {}", e);
+                process::exit(1);
+            }
+        };
+    }
+
+    if cli.empty {
+        filter = filter.only_empty();
+    } else if cli.non_empty {
+        filter = filter.only_non_empty();
+    }
+    filter = filter.empty_ignores_hidden(cli.empty_ignore_hidden);
+
+    // Apply sorting
     let sort_order = match SortOrder::from_str(&cli.order) {
         Some(order) => order,
         None => {
@@ -90,25 +396,288 @@ fn main() {
         }
     };
 
-    sort_entries(&mut entries, sort_field, sort_order);
+    let sort_keys = match SortKey::parse_list(&cli.sort, sort_order) {
+        Ok(keys) => keys,
+        Err(key) => {
+            eprintln!("Error: Invalid sort field: {}", key);
+            process::exit(1);
+        }
+    };
+
+    let group_dirs = match DirGrouping::from_str(&cli.group_dirs) {
+        Some(grouping) => grouping,
+        None => {
+            eprintln!("Error: Invalid directory grouping: {}", cli.group_dirs);
+            process::exit(1);
+        }
+    };
+
+    let collation = match Collation::from_str(&cli.collation) {
+        Some(collation) => collation,
+        None => {
+            eprintln!("Error: Invalid collation: {}", cli.collation);
+            process::exit(1);
+        }
+    };
+
+    let charset = match TreeCharset::from_str(&cli.charset) {
+        Some(charset) => charset,
+        None => {
+            eprintln!("Error: Invalid charset: {}", cli.charset);
+            process::exit(1);
+        }
+    };
+
+    let sorter = Sorter::new(sort_keys)
+        .group_dirs(group_dirs)
+        .collation(collation);
+
+    #[cfg(feature = "tui")]
+    if cli.command == Some(Command::Tui) {
+        run_browser(&cli, &path, &filter, sorter);
+        return;
+    }
+
+    let format = if cli.one_per_line || cli.print0 {
+        "lines".to_string()
+    } else {
+        cli.format.to_lowercase()
+    };
+    // Charts size directories by everything below them, so they always recurse
+    let is_chart = matches!(format.as_str(), "treemap-svg" | "sunburst-svg");
+    let max_depth = if cli.recursive || is_chart {
+        cli.max_depth.unwrap_or(usize::MAX)
+    } else {
+        0
+    };
+
+    // NDJSON is written while walking, sorting one directory at a time,
+    // unless a global sort of a recursive listing is requested
+    if format == "ndjson" && !(cli.recursive && cli.flat_sort) {
+        let mut summary = ListingSummary::default();
+        let mut errors = Vec::new();
+        let mut skipped = Vec::new();
+        let mut out = io::stdout().lock();
+        let result = walk(
+            &path,
+            max_depth,
+            &|dir_entries| sorter.sort(dir_entries),
+            &mut |entry| match filter.apply(entry) {
+                Some(entry) => match write_ndjson_entry(&mut out, &entry) {
+                    Ok(()) => {
+                        summary.add_entry(&entry);
+                        Ok(())
+                    }
+                    // One bad entry is reported and skipped instead of ending the stream
+                    Err(e) if e.kind() == io::ErrorKind::InvalidData => {
+                        skipped.push(WalkError::new(entry.path(), e));
+                        Ok(())
+                    }
+                    Err(e) => Err(e),
+                },
+                None => Ok(()),
+            },
+            &mut |path, e| errors.push(WalkError::new(path, e)),
+        );
+        finish_output(result.and_then(|_| {
+            if cli.summary {
+                for error in errors.into_iter().chain(skipped) {
+                    summary.add_error(error);
+                }
+                write_ndjson_summary(&mut out, &summary)?;
+            } else {
+                for error in skipped {
+                    eprintln!(
+                        "Warning: Cannot serialize {:?}: {}",
+                        error.path, error.error
+                    );
+                }
+            }
+            out.flush()
+        }));
+        return;
+    }
+
+    let mut entries = Vec::new();
+    let mut errors = Vec::new();
+    walk(
+        &path,
+        max_depth,
+        &|_| {},
+        &mut |entry| {
+            entries.push(entry);
+            Ok(())
+        },
+        &mut |path, e| errors.push(WalkError::new(path, e)),
+    )
+    .expect("collecting entries cannot fail");
+    entries = filter_entries(entries, &filter);
+
+    // Recursive tree and long output keep each directory's contents together
+    if cli.recursive
+        && !cli.flat_sort
+        && matches!(format.as_str(), "tree" | "long" | "markdown-tree")
+    {
+        sorter.sort_hierarchical(&mut entries);
+    } else {
+        sorter.sort(&mut entries);
+    }
 
     // Setup color theme
     let color_theme = ColorTheme::new(!cli.no_color);
 
-    // Print summary if requested
-    if cli.summary {
-        print_summary(&entries);
-    }
+    // --si implies human-readable sizes, like GNU ls
+    let size_format = SizeFormat::new(cli.si, cli.size_precision);
+    let human_size = (cli.human_readable || cli.si).then_some(size_format);
 
-    // Print output
-    match cli.format.to_lowercase().as_str() {
-        "json" => print_json(entries),
-        "table" => print_table(entries, cli.human_readable, &color_theme),
-        "long" => print_long(entries, cli.human_readable, &color_theme),
-        "tree" => print_tree(entries, cli.human_readable, &color_theme),
+    // Pick the output format; renderers borrow their settings from these
+    let envelope;
+    let columns;
+    let template;
+    let renderer: Box<dyn Renderer> = match format.as_str() {
+        "json" => {
+            envelope = json_envelope(&cli, &path, &format);
+            Box::new(JsonRenderer::new(&envelope))
+        }
+        "json-tree" => {
+            envelope = json_envelope(&cli, &path, &format);
+            Box::new(JsonTreeRenderer::new(&envelope).dir_totals(cli.dir_totals))
+        }
+        "ndjson" => {
+            let summary = cli.summary.then(|| {
+                let mut summary = ListingSummary::default();
+                for error in errors {
+                    summary.add_error(error);
+                }
+                summary
+            });
+            Box::new(NdjsonRenderer::new().summary(summary))
+        }
+        "csv" => {
+            columns = resolve_columns(&cli, &DEFAULT_EXPORT_COLUMNS);
+            Box::new(DelimitedRenderer::csv(&columns))
+        }
+        "tsv" => {
+            columns = resolve_columns(&cli, &DEFAULT_EXPORT_COLUMNS);
+            Box::new(DelimitedRenderer::tsv(&columns))
+        }
+        "template" => {
+            let Some(spec) = cli.template.as_deref() else {
+                eprintln!("Error: --format template requires --template");
+                process::exit(1);
+            };
+            template = match Template::parse(spec) {
+                Ok(template) => template,
+                Err(e) => {
+                    eprintln!("Error: Invalid template: {}", e);
+                    process::exit(1);
+                }
+            };
+            Box::new(TemplateRenderer::new(&template, size_format, &color_theme))
+        }
+        "html" => {
+            columns = resolve_columns(&cli, &DEFAULT_TABLE_COLUMNS);
+            Box::new(HtmlRenderer::new(&path, &columns).size_format(size_format))
+        }
+        "treemap-svg" | "sunburst-svg" => {
+            let chart = if format == "treemap-svg" {
+                Chart::Treemap
+            } else {
+                Chart::Sunburst
+            };
+            let chart_color = match ChartColor::from_str(&cli.chart_color) {
+                Some(color) => color,
+                None => {
+                    eprintln!("Error: Invalid chart color: {}", cli.chart_color);
+                    process::exit(1);
+                }
+            };
+            Box::new(
+                ChartRenderer::new(&path, chart)
+                    .color_by(chart_color)
+                    .size_format(size_format),
+            )
+        }
+        "grid" => Box::new(GridRenderer::new(&color_theme).across(cli.across)),
+        "lines" => {
+            let quoting = match QuotingStyle::from_str(&cli.quoting_style) {
+                Some(style) => style,
+                None => {
+                    eprintln!("Error: Invalid quoting style: {}", cli.quoting_style);
+                    process::exit(1);
+                }
+            };
+            Box::new(
+                LinesRenderer::new(&path)
+                    .absolute(cli.absolute)
+                    .quoting(quoting)
+                    .null_terminated(cli.print0),
+            )
+        }
+        "table" => {
+            columns = resolve_columns(&cli, &DEFAULT_TABLE_COLUMNS);
+            Box::new(
+                TableRenderer::new(&columns, &color_theme)
+                    .size_format(human_size)
+                    .bars(cli.bars)
+                    .charset(charset),
+            )
+        }
+        "long" => {
+            columns = resolve_columns(&cli, &DEFAULT_LONG_COLUMNS);
+            Box::new(
+                LongRenderer::new(&columns, &color_theme)
+                    .size_format(human_size)
+                    .bars(cli.bars)
+                    .charset(charset),
+            )
+        }
+        "markdown" => {
+            columns = resolve_columns(&cli, &DEFAULT_TABLE_COLUMNS);
+            Box::new(
+                TableRenderer::new(&columns, &color_theme)
+                    .size_format(human_size)
+                    .markdown(true),
+            )
+        }
+        "markdown-tree" => Box::new(MarkdownTreeRenderer::new().size_format(human_size)),
+        "tree" => Box::new(
+            TreeRenderer::new(&color_theme)
+                .size_format(human_size)
+                .bars(cli.bars)
+                .dir_totals(cli.dir_totals)
+                .collapse(cli.tree_collapse)
+                .dirs_first(cli.tree_dirs_first)
+                .charset(charset),
+        ),
         _ => {
             eprintln!("Error: Invalid format: {}", cli.format);
             process::exit(1);
         }
+    };
+
+    let mut out = BufferedStandardStream::stdout(color_theme.color_choice());
+
+    // Print summary if requested. Data formats keep stdout parseable, so their
+    // summary goes to stderr after the listing; HTML and SVG output must stay a
+    // single document, and the HTML report has its own summary section
+    let is_document = matches!(format.as_str(), "html" | "treemap-svg" | "sunburst-svg");
+    let is_data = matches!(
+        format.as_str(),
+        "json" | "json-tree" | "csv" | "tsv" | "lines" | "template"
+    );
+    let summary = cli.summary && format != "ndjson" && !is_document;
+    let result = if summary && !is_data {
+        print_summary(&mut out, &entries, size_format)
+    } else {
+        Ok(())
+    };
+    finish_output(
+        result
+            .and_then(|_| renderer.render(&entries, &mut out))
+            .and_then(|_| out.flush()),
+    );
+    if summary && is_data {
+        let _ = print_summary(&mut io::stderr().lock(), &entries, size_format);
     }
 }
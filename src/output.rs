@@ -1,179 +1,1530 @@
+use crate::bars::{SizeShares, ascii_bar, bar_width, percent, size_bar};
 use crate::colors::ColorTheme;
+use crate::columns::Column;
 use crate::file_entry::FileEntry;
-use crate::size_utils::HumanSize;
+use crate::file_ops::WalkError;
+use crate::quoting::QuotingStyle;
+use crate::schema::Envelope;
+use crate::size_utils::SizeFormat;
+use crate::template::Template;
+use crate::terminal::terminal_width;
+use crate::tree::{DirTotals, EntryTree};
+use serde::Serialize;
 use serde_json;
+use std::borrow::Cow;
+use std::io::{self, Write};
 use std::path::Path;
-use tabled::Table;
+use tabled::builder::Builder;
 use tabled::settings::{
-    Color, Modify, Style, Width,
+    Alignment, Color, Modify, Style, Width,
     object::{Columns, Rows},
 };
+use termcolor::WriteColor;
+use unicode_width::UnicodeWidthStr;
 
-/// Print the result in table format
-pub fn print_table(entries: Vec<FileEntry>, human_readable: bool, color_theme: &ColorTheme) {
-    let entries: Vec<TableEntry> = entries
-        .into_iter()
-        .map(|e| TableEntry::new(e, human_readable))
+/// Writes a listing to an output stream
+pub trait Renderer {
+    /// Write `entries` to `out`, stopping at the first write error
+    fn render(&self, entries: &[FileEntry], out: &mut dyn WriteColor) -> io::Result<()>;
+}
+
+/// A column of table and long output: an entry field, or with `--bars`
+/// a size bar or the percentage of the parent directory's size
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Field {
+    Column(Column),
+    Bar,
+    Percent,
+}
+
+impl Field {
+    /// The columns, with the bar and percentage inserted before a trailing name
+    fn list(columns: &[Column], bars: bool) -> Vec<Field> {
+        let mut fields: Vec<Field> = columns.iter().map(|&c| Field::Column(c)).collect();
+        if bars {
+            let at = match columns.last() {
+                Some(Column::Name) => fields.len() - 1,
+                _ => fields.len(),
+            };
+            fields.splice(at..at, [Field::Bar, Field::Percent]);
+        }
+        fields
+    }
+
+    fn title(&self) -> &'static str {
+        match self {
+            Field::Column(column) => column.title(),
+            Field::Bar => "Usage",
+            Field::Percent => "%",
+        }
+    }
+
+    fn is_numeric(&self) -> bool {
+        match self {
+            Field::Column(column) => column.is_numeric(),
+            Field::Bar => false,
+            Field::Percent => true,
+        }
+    }
+}
+
+/// Cells of every entry for `fields`. Bars are drawn with `charset` in the
+/// terminal columns the other cells leave, after `gap` columns between cells.
+fn field_rows(
+    fields: &[Field],
+    entries: &[FileEntry],
+    size_format: Option<SizeFormat>,
+    shares: Option<&SizeShares>,
+    charset: TreeCharset,
+    gap: usize,
+) -> Vec<Vec<String>> {
+    let mut rows: Vec<Vec<String>> = entries
+        .iter()
+        .enumerate()
+        .map(|(i, entry)| {
+            fields
+                .iter()
+                .map(|field| match (field, shares) {
+                    // With bars, directories show the size of everything below them
+                    (Field::Column(Column::Size), Some(shares)) if entry.is_dir() => {
+                        match size_format {
+                            Some(size_format) => size_format.format(shares.size(i)),
+                            None => shares.size(i).to_string(),
+                        }
+                    }
+                    (Field::Column(column), _) => column.display_value(entry, size_format),
+                    (Field::Percent, Some(shares)) => percent(shares.share(i)),
+                    (Field::Bar, _) | (Field::Percent, None) => String::new(),
+                })
+                .collect()
+        })
         .collect();
 
-    let mut table = Table::new(entries);
+    if let (Some(shares), Some(bar)) = (shares, fields.iter().position(|f| *f == Field::Bar)) {
+        let used: usize = fields
+            .iter()
+            .enumerate()
+            .filter(|(_, field)| **field != Field::Bar)
+            .map(|(j, field)| {
+                rows.iter()
+                    .map(|row| row[j].width())
+                    .chain([field.title().width()])
+                    .max()
+                    .unwrap_or_default()
+                    + gap
+            })
+            .sum();
+        let width = bar_width(terminal_width().saturating_sub(used + gap));
+        for (i, row) in rows.iter_mut().enumerate() {
+            row[bar] = charset.bar(shares.share(i), width);
+        }
+    }
+    rows
+}
 
-    // 设置表格样式
-    table.with(Style::modern_rounded()).with(Width::wrap(100));
+/// Bordered table with one row per entry
+pub struct TableRenderer<'a> {
+    columns: &'a [Column],
+    size_format: Option<SizeFormat>,
+    color_theme: &'a ColorTheme,
+    markdown: bool,
+    bars: bool,
+    charset: TreeCharset,
+}
 
-    // 只在启用颜色时应用颜色样式
-    if color_theme.is_enabled() {
+impl<'a> TableRenderer<'a> {
+    pub fn new(columns: &'a [Column], color_theme: &'a ColorTheme) -> Self {
+        Self {
+            columns,
+            size_format: None,
+            color_theme,
+            markdown: false,
+            bars: false,
+            charset: TreeCharset::default(),
+        }
+    }
+
+    /// Show sizes in human-readable form
+    pub fn size_format(mut self, size_format: Option<SizeFormat>) -> Self {
+        self.size_format = size_format;
+        self
+    }
+
+    /// Write a GitHub-flavored Markdown table instead, uncolored and not
+    /// wrapped to the terminal
+    pub fn markdown(mut self, markdown: bool) -> Self {
+        self.markdown = markdown;
+        self
+    }
+
+    /// Add a size bar and the percentage of the parent directory's size
+    pub fn bars(mut self, bars: bool) -> Self {
+        self.bars = bars;
+        self
+    }
+
+    /// Characters the size bars are drawn with
+    pub fn charset(mut self, charset: TreeCharset) -> Self {
+        self.charset = charset;
+        self
+    }
+}
+
+impl Renderer for TableRenderer<'_> {
+    fn render(&self, entries: &[FileEntry], out: &mut dyn WriteColor) -> io::Result<()> {
+        let fields = Field::list(self.columns, self.bars);
+        let shares = self.bars.then(|| SizeShares::new(entries));
+        // Cell padding and the border between cells
+        let rows = field_rows(
+            &fields,
+            entries,
+            self.size_format,
+            shares.as_ref(),
+            self.charset,
+            3,
+        );
+
+        let mut builder = Builder::with_capacity(entries.len() + 1, fields.len());
+        builder.push_record(fields.iter().map(|f| f.title()));
+        for row in rows {
+            if self.markdown {
+                builder.push_record(row.iter().map(|v| markdown_escape(v).into_owned()));
+            } else {
+                builder.push_record(row);
+            }
+        }
+        let mut table = builder.build();
+
+        if self.markdown {
+            table.with(Style::markdown());
+            for (i, field) in fields.iter().enumerate() {
+                if field.is_numeric() {
+                    table.with(Modify::new(Columns::new(i..i + 1)).with(Alignment::right()));
+                }
+            }
+            return writeln!(out, "{}", table);
+        }
+
+        // 设置表格样式
         table
+            .with(Style::modern_rounded())
+            .with(Width::wrap(terminal_width()));
+
+        // 只在启用颜色时应用颜色样式
+        if self.color_theme.is_enabled() {
             // 设置表头颜色为亮青色
-            .with(Modify::new(Rows::first()).with(Color::FG_BRIGHT_CYAN))
-            // 设置名称列为亮蓝色
-            .with(Modify::new(Columns::first()).with(Color::FG_BRIGHT_BLUE))
-            // 设置类型列为亮黄色
-            .with(Modify::new(Columns::new(1..2)).with(Color::FG_YELLOW))
-            // 设置大小列为亮洋红色
-            .with(Modify::new(Columns::new(2..3)).with(Color::FG_MAGENTA))
-            // 设置修改时间列为亮绿色
-            .with(Modify::new(Columns::new(3..4)).with(Color::FG_GREEN))
-            // 设置权限列为亮白色
-            .with(Modify::new(Columns::new(4..5)).with(Color::FG_WHITE));
-    }
-
-    println!("{}", table);
-}
-
-/// Print the result in long format (similar to ls -l)
-pub fn print_long(entries: Vec<FileEntry>, human_readable: bool, color_theme: &ColorTheme) {
-    println!(
-        " {:10} {:8} {:8} {:>8} {:19} {}",
-        "Permissions", "Owner", "Group", "Size", "Modified", "Name"
-    );
-    println!(" {}", "-".repeat(60));
-
-    for entry in entries {
-        let size = if human_readable {
-            format!("{}", HumanSize(entry.size()))
-        } else {
-            entry.size().to_string()
-        };
+            table.with(Modify::new(Rows::first()).with(Color::FG_BRIGHT_CYAN));
+            for (i, field) in fields.iter().enumerate() {
+                let color = match field {
+                    Field::Column(column) => table_column_color(*column),
+                    Field::Bar => Some(Color::FG_MAGENTA),
+                    Field::Percent => None,
+                };
+                if let Some(color) = color {
+                    table.with(Modify::new(Columns::new(i..i + 1)).with(color));
+                }
+            }
+        }
 
-        print!(
-            " {:10} {:8} {:8} {:>8} {:19} ",
-            entry.permissions(),
-            entry.owner().unwrap_or_default(),
-            entry.group().unwrap_or_default(),
-            size,
-            entry.modified().format("%Y-%m-%d %H:%M:%S")
+        writeln!(out, "{}", table)
+    }
+}
+
+/// 表格中各列的颜色：名称亮蓝、类型黄、大小洋红、时间绿、权限白
+fn table_column_color(column: Column) -> Option<Color> {
+    match column {
+        Column::Name | Column::Path | Column::FullPath => Some(Color::FG_BRIGHT_BLUE),
+        Column::Type => Some(Color::FG_YELLOW),
+        Column::Size | Column::DiskUsage => Some(Color::FG_MAGENTA),
+        Column::Modified | Column::Created | Column::Accessed | Column::Changed => {
+            Some(Color::FG_GREEN)
+        }
+        Column::Permissions | Column::Mode => Some(Color::FG_WHITE),
+        _ => None,
+    }
+}
+
+/// Escape characters that Markdown would read as table separators, code spans,
+/// emphasis or HTML, and flatten line breaks that would end a table row
+fn markdown_escape(value: &str) -> Cow<'_, str> {
+    if !value.contains(['\\', '|', '`', '*', '_', '<', '\n', '\r']) {
+        return Cow::Borrowed(value);
+    }
+    let mut escaped = String::with_capacity(value.len() + 4);
+    for c in value.chars() {
+        match c {
+            '\\' | '|' | '`' | '*' | '_' | '<' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            '\n' | '\r' => escaped.push(' '),
+            _ => escaped.push(c),
+        }
+    }
+    Cow::Owned(escaped)
+}
+
+/// Aligned columns without borders, similar to `ls -l`
+pub struct LongRenderer<'a> {
+    columns: &'a [Column],
+    size_format: Option<SizeFormat>,
+    color_theme: &'a ColorTheme,
+    bars: bool,
+    charset: TreeCharset,
+}
+
+impl<'a> LongRenderer<'a> {
+    pub fn new(columns: &'a [Column], color_theme: &'a ColorTheme) -> Self {
+        Self {
+            columns,
+            size_format: None,
+            color_theme,
+            bars: false,
+            charset: TreeCharset::default(),
+        }
+    }
+
+    /// Show sizes in human-readable form
+    pub fn size_format(mut self, size_format: Option<SizeFormat>) -> Self {
+        self.size_format = size_format;
+        self
+    }
+
+    /// Add a size bar and the percentage of the parent directory's size
+    pub fn bars(mut self, bars: bool) -> Self {
+        self.bars = bars;
+        self
+    }
+
+    /// Characters the size bars are drawn with
+    pub fn charset(mut self, charset: TreeCharset) -> Self {
+        self.charset = charset;
+        self
+    }
+}
+
+impl Renderer for LongRenderer<'_> {
+    fn render(&self, entries: &[FileEntry], out: &mut dyn WriteColor) -> io::Result<()> {
+        let fields = Field::list(self.columns, self.bars);
+        let shares = self.bars.then(|| SizeShares::new(entries));
+        let rows = field_rows(
+            &fields,
+            entries,
+            self.size_format,
+            shares.as_ref(),
+            self.charset,
+            1,
         );
+        let widths: Vec<usize> = fields
+            .iter()
+            .enumerate()
+            .map(|(i, field)| {
+                rows.iter()
+                    .map(|row| row[i].width())
+                    .chain([field.title().width()])
+                    .max()
+                    .unwrap_or_default()
+            })
+            .collect();
 
-        color_theme
-            .print_colored(entry.name(), entry.path())
-            .unwrap();
-        println!();
+        let pad = |text: &str, field: Field, width: usize| {
+            let padding = " ".repeat(width.saturating_sub(text.width()));
+            if field.is_numeric() {
+                format!("{}{}", padding, text)
+            } else {
+                format!("{}{}", text, padding)
+            }
+        };
+
+        let header: Vec<String> = fields
+            .iter()
+            .zip(&widths)
+            .map(|(field, &width)| pad(field.title(), *field, width))
+            .collect();
+        writeln!(out, " {}", header.join(" ").trim_end())?;
+        writeln!(
+            out,
+            " {}",
+            "-".repeat(widths.iter().sum::<usize>() + widths.len().saturating_sub(1))
+        )?;
+
+        for (entry, row) in entries.iter().zip(rows) {
+            write!(out, " ")?;
+            for (i, ((field, value), &width)) in fields.iter().zip(row).zip(&widths).enumerate() {
+                if i > 0 {
+                    write!(out, " ")?;
+                }
+                let is_last = i + 1 == fields.len();
+                if *field == Field::Column(Column::Name) {
+                    self.color_theme.write_colored(out, &value, entry.path())?;
+                    if !is_last {
+                        write!(out, "{}", " ".repeat(width.saturating_sub(value.width())))?;
+                    }
+                } else if is_last && !field.is_numeric() {
+                    write!(out, "{}", value.trim_end())?;
+                } else {
+                    write!(out, "{}", pad(&value, *field, width))?;
+                }
+            }
+            writeln!(out)?;
+        }
+        Ok(())
+    }
+}
+
+/// Characters used to draw tree guide lines and size bars
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum TreeCharset {
+    #[default]
+    Unicode,
+    /// Plain ASCII for terminals and logs that mangle box-drawing characters
+    Ascii,
+}
+
+impl TreeCharset {
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s.to_lowercase().as_str() {
+            "unicode" | "utf-8" | "utf8" => Some(TreeCharset::Unicode),
+            "ascii" => Some(TreeCharset::Ascii),
+            _ => None,
+        }
+    }
+
+    /// Guide for an entry followed by siblings, for the last entry, and the
+    /// indents below them
+    fn guides(&self) -> [&'static str; 4] {
+        match self {
+            TreeCharset::Unicode => ["├── ", "└── ", "│   ", "    "],
+            TreeCharset::Ascii => ["|-- ", "`-- ", "|   ", "    "],
+        }
+    }
+
+    fn ellipsis(&self) -> &'static str {
+        match self {
+            TreeCharset::Unicode => "…",
+            TreeCharset::Ascii => "...",
+        }
     }
+
+    /// Size bar of `width` columns filled in proportion to `share`
+    fn bar(&self, share: f64, width: usize) -> String {
+        match self {
+            TreeCharset::Unicode => size_bar(share, width),
+            TreeCharset::Ascii => ascii_bar(share, width),
+        }
+    }
+}
+
+/// Indented tree drawn with box-drawing characters
+pub struct TreeRenderer<'a> {
+    size_format: Option<SizeFormat>,
+    color_theme: &'a ColorTheme,
+    bars: bool,
+    dir_totals: bool,
+    collapse: Option<usize>,
+    dirs_first: bool,
+    charset: TreeCharset,
+}
+
+/// Size bars of a tree: the shares of all entries and the bar width
+struct TreeBars {
+    shares: SizeShares,
+    width: usize,
+}
+
+/// What rendering a tree needs besides the renderer's settings
+struct TreeContext<'e> {
+    entries: &'e [FileEntry],
+    tree: EntryTree,
+    totals: Vec<DirTotals>,
+    bars: Option<TreeBars>,
 }
 
-/// Print the result in tree format
-pub fn print_tree(entries: Vec<FileEntry>, human_readable: bool, color_theme: &ColorTheme) {
-    fn print_tree_recursive(
-        entries: &[FileEntry],
-        path: &Path,
+impl<'a> TreeRenderer<'a> {
+    pub fn new(color_theme: &'a ColorTheme) -> Self {
+        Self {
+            size_format: None,
+            color_theme,
+            bars: false,
+            dir_totals: false,
+            collapse: None,
+            dirs_first: false,
+            charset: TreeCharset::default(),
+        }
+    }
+
+    /// Show each entry's size after its name
+    pub fn size_format(mut self, size_format: Option<SizeFormat>) -> Self {
+        self.size_format = size_format;
+        self
+    }
+
+    /// Start each line with the percentage of the parent directory's size and a
+    /// size bar; directory sizes include everything below them
+    pub fn bars(mut self, bars: bool) -> Self {
+        self.bars = bars;
+        self
+    }
+
+    /// Show the total size and number of files below each directory
+    pub fn dir_totals(mut self, dir_totals: bool) -> Self {
+        self.dir_totals = dir_totals;
+        self
+    }
+
+    /// Show only the first `limit` entries of larger directories, followed by a
+    /// line counting the rest
+    pub fn collapse(mut self, limit: Option<usize>) -> Self {
+        self.collapse = limit;
+        self
+    }
+
+    /// List the subdirectories of each directory before its files
+    pub fn dirs_first(mut self, dirs_first: bool) -> Self {
+        self.dirs_first = dirs_first;
+        self
+    }
+
+    pub fn charset(mut self, charset: TreeCharset) -> Self {
+        self.charset = charset;
+        self
+    }
+
+    /// Percentage and bar that start a line, when bars are shown
+    fn write_bar(&self, ctx: &TreeContext, share: f64, out: &mut dyn WriteColor) -> io::Result<()> {
+        if let Some(bars) = &ctx.bars {
+            let bar = self.charset.bar(share, bars.width);
+            write!(out, "{:>6} {} ", percent(share), bar)?;
+        }
+        Ok(())
+    }
+
+    fn render_children(
+        &self,
+        ctx: &TreeContext,
+        children: &[usize],
+        prefix: &str,
+        out: &mut dyn WriteColor,
+    ) -> io::Result<()> {
+        let mut children = children.to_vec();
+        if self.dirs_first {
+            children.sort_by_key(|&i| !ctx.entries[i].is_dir());
+        }
+        let shown = match self.collapse {
+            Some(limit) if children.len() > limit => limit,
+            _ => children.len(),
+        };
+        let (shown, hidden) = children.split_at(shown);
+
+        for (i, &child) in shown.iter().enumerate() {
+            let is_last = i + 1 == shown.len() && hidden.is_empty();
+            self.render_node(ctx, child, prefix, is_last, out)?;
+        }
+
+        if !hidden.is_empty() {
+            let size: u64 = hidden.iter().map(|&i| ctx.totals[i].size).sum();
+            let share = ctx.bars.as_ref().map_or(0.0, |bars| {
+                hidden.iter().map(|&i| bars.shares.share(i)).sum()
+            });
+            let noun = match (
+                hidden.len(),
+                hidden.iter().any(|&i| ctx.entries[i].is_dir()),
+            ) {
+                (1, false) => "file",
+                (1, true) => "entry",
+                (_, false) => "files",
+                (_, true) => "entries",
+            };
+            self.write_bar(ctx, share, out)?;
+            writeln!(
+                out,
+                "{}{}{} {} more {} ({})",
+                prefix,
+                self.charset.guides()[1],
+                self.charset.ellipsis(),
+                group_thousands(hidden.len()),
+                noun,
+                self.size_format.unwrap_or_default().format(size)
+            )?;
+        }
+        Ok(())
+    }
+
+    fn render_node(
+        &self,
+        ctx: &TreeContext,
+        index: usize,
         prefix: &str,
         is_last: bool,
-        human_readable: bool,
-        color_theme: &ColorTheme,
-    ) {
-        let entry = entries.iter().find(|e| e.path() == path).unwrap();
+        out: &mut dyn WriteColor,
+    ) -> io::Result<()> {
+        let entry = &ctx.entries[index];
+        let [branch, last, vertical, blank] = self.charset.guides();
 
         // Print current entry
-        print!("{}", prefix);
-        print!("{}", if is_last { "└── " } else { "├── " });
+        let share = ctx
+            .bars
+            .as_ref()
+            .map_or(0.0, |bars| bars.shares.share(index));
+        self.write_bar(ctx, share, out)?;
+        write!(out, "{}", prefix)?;
+        write!(out, "{}", if is_last { last } else { branch })?;
 
-        color_theme
-            .print_colored(entry.name(), entry.path())
-            .unwrap();
+        self.color_theme
+            .write_colored(out, entry.name(), entry.path())?;
 
-        if human_readable {
-            print!(" ({})", HumanSize(entry.size()));
+        let totals = ctx.totals[index];
+        // A directory whose contents were not listed has no totals to claim
+        if entry.is_dir() && self.dir_totals && ctx.tree.children(index).is_empty() {
+            write!(
+                out,
+                " ({})",
+                self.size_format.unwrap_or_default().format(totals.size)
+            )?;
+        } else if entry.is_dir() && self.dir_totals {
+            write!(
+                out,
+                " ({}, {} {})",
+                self.size_format.unwrap_or_default().format(totals.size),
+                group_thousands(totals.files),
+                if totals.files == 1 { "file" } else { "files" }
+            )?;
+        } else if let Some(size_format) = self.size_format {
+            // With bars, show the size the bar is drawn from
+            let size = ctx
+                .bars
+                .as_ref()
+                .map_or(entry.size(), |bars| bars.shares.size(index));
+            write!(out, " ({})", size_format.format(size))?;
         }
-        println!();
+        writeln!(out)?;
 
-        // Get children
-        let children: Vec<_> = entries
-            .iter()
-            .filter(|e| e.path().parent() == Some(path))
-            .collect();
+        let children = ctx.tree.children(index);
+        if children.is_empty() {
+            return Ok(());
+        }
+        let new_prefix = format!("{}{}", prefix, if is_last { blank } else { vertical });
+        self.render_children(ctx, children, &new_prefix, out)
+    }
+}
+
+impl Renderer for TreeRenderer<'_> {
+    fn render(&self, entries: &[FileEntry], out: &mut dyn WriteColor) -> io::Result<()> {
+        // Index parents and children once so rendering stays linear
+        let tree = EntryTree::new(entries);
+
+        let bars = self.bars.then(|| {
+            // Leave room for the widest line: guides, name and size
+            let longest = entries
+                .iter()
+                .map(|e| {
+                    let size = self.size_format.map_or(0, |f| f.format(e.size()).len() + 3);
+                    4 * (e.depth() + 1) + e.name().width() + size
+                })
+                .max()
+                .unwrap_or_default();
+            TreeBars {
+                shares: SizeShares::from_tree(entries, &tree),
+                width: bar_width(terminal_width().saturating_sub(longest + 8)),
+            }
+        });
+        let ctx = TreeContext {
+            entries,
+            totals: tree.listed_totals(entries),
+            tree,
+            bars,
+        };
 
-        let child_count = children.len();
-        for (i, child) in children.iter().enumerate() {
-            let new_prefix = format!("{}{}", prefix, if is_last { "    " } else { "│   " });
-            print_tree_recursive(
-                entries,
-                child.path(),
-                &new_prefix,
-                i == child_count - 1,
-                human_readable,
-                color_theme,
-            );
+        self.render_children(&ctx, ctx.tree.roots(), "", out)
+    }
+}
+
+/// Format a count with thousands separators, e.g. `1,204`
+fn group_thousands(n: usize) -> String {
+    let digits = n.to_string();
+    let mut grouped = String::with_capacity(digits.len() + digits.len() / 3);
+    for (i, c) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            grouped.push(',');
         }
+        grouped.push(c);
     }
+    grouped
+}
 
-    // Find root entries (those without parents in the list)
-    let root_entries: Vec<_> = entries
-        .iter()
-        .filter(|e| !entries.iter().any(|p| e.path().parent() == Some(p.path())))
-        .collect();
+/// Tree as a nested Markdown list, directories in bold with a trailing slash
+pub struct MarkdownTreeRenderer {
+    size_format: Option<SizeFormat>,
+}
 
-    for (i, entry) in root_entries.iter().enumerate() {
-        print_tree_recursive(
-            &entries,
-            entry.path(),
-            "",
-            i == root_entries.len() - 1,
-            human_readable,
+impl MarkdownTreeRenderer {
+    pub fn new() -> Self {
+        Self { size_format: None }
+    }
+
+    /// Show each entry's size after its name
+    pub fn size_format(mut self, size_format: Option<SizeFormat>) -> Self {
+        self.size_format = size_format;
+        self
+    }
+}
+
+impl Default for MarkdownTreeRenderer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Renderer for MarkdownTreeRenderer {
+    fn render(&self, entries: &[FileEntry], out: &mut dyn WriteColor) -> io::Result<()> {
+        let tree = EntryTree::new(entries);
+        let mut stack: Vec<(usize, usize)> = tree.roots().iter().rev().map(|&n| (n, 0)).collect();
+        while let Some((node, level)) = stack.pop() {
+            let entry = &entries[node];
+            let name = markdown_escape(entry.name());
+            write!(out, "{}- ", "  ".repeat(level))?;
+            if entry.is_dir() {
+                write!(out, "**{}/**", name)?;
+            } else {
+                write!(out, "{}", name)?;
+            }
+            if let Some(size_format) = self.size_format {
+                write!(out, " ({})", size_format.format(entry.size()))?;
+            }
+            writeln!(out)?;
+            stack.extend(tree.children(node).iter().rev().map(|&n| (n, level + 1)));
+        }
+        Ok(())
+    }
+}
+
+/// Spaces between grid columns
+const GRID_GAP: usize = 2;
+
+/// Column layout of a grid: number of rows and the width of each column
+#[derive(Debug, PartialEq)]
+pub struct GridLayout {
+    pub rows: usize,
+    pub column_widths: Vec<usize>,
+}
+
+impl GridLayout {
+    /// Fit items with the given display widths into as many columns as `max_width` allows.
+    /// Items fill columns top to bottom, or rows left to right when `across` is set.
+    pub fn new(widths: &[usize], max_width: usize, across: bool) -> Self {
+        let n = widths.len();
+        if n == 0 {
+            return Self {
+                rows: 0,
+                column_widths: Vec::new(),
+            };
+        }
+
+        // No layout can have more columns than the narrowest possible items allow
+        let min_width = widths.iter().copied().min().unwrap_or(0).max(1);
+        let max_columns = (max_width / (min_width + GRID_GAP) + 1).clamp(1, n);
+
+        for columns in (1..=max_columns).rev() {
+            let rows = n.div_ceil(columns);
+            // Down-then-across may need fewer columns than asked for
+            let columns = if across { columns } else { n.div_ceil(rows) };
+
+            let mut column_widths = vec![0; columns];
+            for (i, &w) in widths.iter().enumerate() {
+                let column = if across { i % columns } else { i / rows };
+                column_widths[column] = column_widths[column].max(w);
+            }
+
+            let total: usize = column_widths.iter().sum::<usize>() + GRID_GAP * (columns - 1);
+            if total <= max_width || columns == 1 {
+                return Self {
+                    rows,
+                    column_widths,
+                };
+            }
+        }
+
+        unreachable!("a single column always fits")
+    }
+
+    /// Index of the item at `row`/`column`, if any
+    pub fn index(&self, row: usize, column: usize, across: bool, len: usize) -> Option<usize> {
+        let index = if across {
+            row * self.column_widths.len() + column
+        } else {
+            column * self.rows + row
+        };
+        (index < len).then_some(index)
+    }
+}
+
+/// Names in columns sized to the terminal, like `ls`
+pub struct GridRenderer<'a> {
+    across: bool,
+    color_theme: &'a ColorTheme,
+}
+
+impl<'a> GridRenderer<'a> {
+    pub fn new(color_theme: &'a ColorTheme) -> Self {
+        Self {
+            across: false,
             color_theme,
-        );
+        }
+    }
+
+    /// Fill rows left to right instead of columns top to bottom
+    pub fn across(mut self, across: bool) -> Self {
+        self.across = across;
+        self
     }
 }
 
-/// Print the result in JSON format
-pub fn print_json(entries: Vec<FileEntry>) {
-    println!(
-        "{}",
-        serde_json::to_string_pretty(&entries)
-            .unwrap_or_else(|_| "Error serializing to JSON".to_string())
-    );
+impl Renderer for GridRenderer<'_> {
+    fn render(&self, entries: &[FileEntry], out: &mut dyn WriteColor) -> io::Result<()> {
+        let across = self.across;
+        let widths: Vec<usize> = entries.iter().map(|e| e.name().width()).collect();
+        let layout = GridLayout::new(&widths, terminal_width(), across);
+        let columns = layout.column_widths.len();
+
+        for row in 0..layout.rows {
+            for column in 0..columns {
+                let Some(i) = layout.index(row, column, across, entries.len()) else {
+                    continue;
+                };
+                let entry = &entries[i];
+
+                self.color_theme
+                    .write_colored(out, entry.name(), entry.path())?;
+
+                // Pad unless this is the last item on the line
+                let is_last = column + 1 == columns
+                    || layout
+                        .index(row, column + 1, across, entries.len())
+                        .is_none();
+                if !is_last {
+                    let padding = layout.column_widths[column] - widths[i] + GRID_GAP;
+                    write!(out, "{:padding$}", "")?;
+                }
+            }
+            writeln!(out)?;
+        }
+        Ok(())
+    }
 }
 
-#[derive(tabled::Tabled)]
-struct TableEntry {
-    #[tabled(rename = "Name")]
-    name: String,
-    #[tabled(rename = "Type")]
-    type_: String,
-    #[tabled(rename = "Size")]
-    size: String,
-    #[tabled(rename = "Modified")]
-    modified: String,
-    #[tabled(rename = "Permissions")]
-    permissions: String,
+/// One path per line, relative to the listed directory or absolute, for piping into
+/// other tools
+pub struct LinesRenderer<'a> {
+    root: &'a Path,
+    absolute: bool,
+    quoting: QuotingStyle,
+    null_terminated: bool,
 }
 
-impl TableEntry {
-    fn new(entry: FileEntry, human_readable: bool) -> Self {
+impl<'a> LinesRenderer<'a> {
+    pub fn new(root: &'a Path) -> Self {
         Self {
-            name: entry.name().to_string(),
-            type_: if entry.is_dir() {
-                "Dir".into()
-            } else {
-                "File".into()
-            },
-            size: if human_readable {
-                HumanSize(entry.size()).to_string()
+            root,
+            absolute: false,
+            quoting: QuotingStyle::default(),
+            null_terminated: false,
+        }
+    }
+
+    /// Write absolute paths instead of paths relative to the root
+    pub fn absolute(mut self, absolute: bool) -> Self {
+        self.absolute = absolute;
+        self
+    }
+
+    pub fn quoting(mut self, quoting: QuotingStyle) -> Self {
+        self.quoting = quoting;
+        self
+    }
+
+    /// End each path with a NUL byte instead of a newline, for `xargs -0`
+    pub fn null_terminated(mut self, null_terminated: bool) -> Self {
+        self.null_terminated = null_terminated;
+        self
+    }
+}
+
+impl Renderer for LinesRenderer<'_> {
+    fn render(&self, entries: &[FileEntry], out: &mut dyn WriteColor) -> io::Result<()> {
+        let terminator = if self.null_terminated { b'\0' } else { b'\n' };
+
+        for entry in entries {
+            let path = if self.absolute {
+                std::path::absolute(entry.path()).unwrap_or_else(|_| entry.path().to_path_buf())
             } else {
-                entry.size().to_string()
-            },
-            modified: entry.modified().format("%Y-%m-%d %H:%M:%S").to_string(),
-            permissions: entry.permissions(),
+                entry
+                    .path()
+                    .strip_prefix(self.root)
+                    .unwrap_or(entry.path())
+                    .to_path_buf()
+            };
+            // Names are written byte for byte so non-UTF-8 paths survive `xargs -0`
+            out.write_all(&self.quoting.quote(path.as_os_str()))?;
+            out.write_all(&[terminator])?;
+        }
+        Ok(())
+    }
+}
+
+/// Each entry formatted with a user-defined template, one per line
+pub struct TemplateRenderer<'a> {
+    template: &'a Template,
+    size_format: SizeFormat,
+    color_theme: &'a ColorTheme,
+}
+
+impl<'a> TemplateRenderer<'a> {
+    pub fn new(
+        template: &'a Template,
+        size_format: SizeFormat,
+        color_theme: &'a ColorTheme,
+    ) -> Self {
+        Self {
+            template,
+            size_format,
+            color_theme,
         }
     }
 }
+
+impl Renderer for TemplateRenderer<'_> {
+    fn render(&self, entries: &[FileEntry], out: &mut dyn WriteColor) -> io::Result<()> {
+        for entry in entries {
+            self.template
+                .render(entry, self.size_format, self.color_theme, out)?;
+            writeln!(out)?;
+        }
+        Ok(())
+    }
+}
+
+/// Pretty-printed JSON document wrapped in the versioned envelope
+pub struct JsonRenderer<'a> {
+    envelope: &'a Envelope,
+}
+
+impl<'a> JsonRenderer<'a> {
+    pub fn new(envelope: &'a Envelope) -> Self {
+        Self { envelope }
+    }
+}
+
+impl Renderer for JsonRenderer<'_> {
+    fn render(&self, entries: &[FileEntry], out: &mut dyn WriteColor) -> io::Result<()> {
+        #[derive(Serialize)]
+        struct Listing<'a> {
+            #[serde(flatten)]
+            envelope: &'a Envelope,
+            entries: &'a [FileEntry],
+        }
+
+        let listing = Listing {
+            envelope: self.envelope,
+            entries,
+        };
+        serde_json::to_writer_pretty(&mut *out, &listing)?;
+        writeln!(out)
+    }
+}
+
+/// A directory or file in `--format json-tree`; directories carry their children
+#[derive(Serialize)]
+struct JsonTreeNode<'a> {
+    #[serde(flatten)]
+    entry: &'a FileEntry,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    total_size: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    file_count: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    children: Option<Vec<JsonTreeNode<'a>>>,
+}
+
+/// One nested JSON object rooted at the envelope's root
+pub struct JsonTreeRenderer<'a> {
+    envelope: &'a Envelope,
+    dir_totals: bool,
+}
+
+impl<'a> JsonTreeRenderer<'a> {
+    pub fn new(envelope: &'a Envelope) -> Self {
+        Self {
+            envelope,
+            dir_totals: false,
+        }
+    }
+
+    /// Give directories the total size and number of files below them
+    pub fn dir_totals(mut self, dir_totals: bool) -> Self {
+        self.dir_totals = dir_totals;
+        self
+    }
+}
+
+impl Renderer for JsonTreeRenderer<'_> {
+    fn render(&self, entries: &[FileEntry], out: &mut dyn WriteColor) -> io::Result<()> {
+        #[derive(Serialize)]
+        struct TreeDocument<'a> {
+            #[serde(flatten)]
+            envelope: &'a Envelope,
+            tree: JsonTreeNode<'a>,
+        }
+
+        fn build<'a>(
+            entries: &'a [FileEntry],
+            tree: &EntryTree,
+            totals: Option<&[DirTotals]>,
+            node: usize,
+        ) -> JsonTreeNode<'a> {
+            let entry = &entries[node];
+            // Directories whose contents were not listed get no totals
+            let dir_totals = totals
+                .filter(|_| entry.is_dir() && !tree.children(node).is_empty())
+                .map(|t| t[node]);
+            JsonTreeNode {
+                entry,
+                total_size: dir_totals.map(|t| t.size),
+                file_count: dir_totals.map(|t| t.files),
+                children: entry.is_dir().then(|| {
+                    tree.children(node)
+                        .iter()
+                        .map(|&child| build(entries, tree, totals, child))
+                        .collect()
+                }),
+            }
+        }
+
+        let root = self.envelope.root();
+        let root_entry = match root.metadata() {
+            Ok(metadata) => FileEntry::from_metadata(root.to_path_buf(), &metadata),
+            Err(_) => FileEntry::new(root.to_path_buf(), true, 0, None, None),
+        };
+        let tree = EntryTree::new(entries);
+        let totals = self.dir_totals.then(|| tree.totals(entries));
+        let root_totals = totals.as_ref().map(|totals| {
+            tree.roots()
+                .iter()
+                .fold(DirTotals::default(), |sum, &node| DirTotals {
+                    size: sum.size + totals[node].size,
+                    files: sum.files + totals[node].files,
+                })
+        });
+
+        let root_node = JsonTreeNode {
+            entry: &root_entry,
+            total_size: root_totals.map(|t| t.size),
+            file_count: root_totals.map(|t| t.files),
+            children: Some(
+                tree.roots()
+                    .iter()
+                    .map(|&node| build(entries, &tree, totals.as_deref(), node))
+                    .collect(),
+            ),
+        };
+
+        let document = TreeDocument {
+            envelope: self.envelope,
+            tree: root_node,
+        };
+        serde_json::to_writer_pretty(&mut *out, &document)?;
+        writeln!(out)
+    }
+}
+
+/// Totals written as the final `--format ndjson` record
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ListingSummary {
+    files: usize,
+    directories: usize,
+    total_size: u64,
+    errors: Vec<WalkError>,
+}
+
+impl ListingSummary {
+    pub fn add_entry(&mut self, entry: &FileEntry) {
+        if entry.is_dir() {
+            self.directories += 1;
+        } else {
+            self.files += 1;
+        }
+        self.total_size += entry.size();
+    }
+
+    pub fn add_error(&mut self, error: WalkError) {
+        self.errors.push(error);
+    }
+}
+
+/// Write a single entry as one compact JSON line. The record is serialized in
+/// full before anything is written, so an entry that cannot be serialized fails
+/// with `InvalidData` and leaves `out` a valid NDJSON stream.
+pub fn write_ndjson_entry(out: &mut dyn Write, entry: &FileEntry) -> io::Result<()> {
+    let mut record = serde_json::to_vec(entry)?;
+    record.push(b'\n');
+    out.write_all(&record)
+}
+
+/// Write the summary as a `{"summary": {...}}` line
+pub fn write_ndjson_summary(out: &mut dyn Write, summary: &ListingSummary) -> io::Result<()> {
+    #[derive(Serialize)]
+    struct SummaryRecord<'a> {
+        summary: &'a ListingSummary,
+    }
+
+    serde_json::to_writer(&mut *out, &SummaryRecord { summary })?;
+    writeln!(out)
+}
+
+/// Newline-delimited JSON, optionally followed by a summary record
+#[derive(Default)]
+pub struct NdjsonRenderer {
+    summary: Option<ListingSummary>,
+}
+
+impl NdjsonRenderer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// End with a summary record; `summary` holds the walk errors and the
+    /// rendered entries are added to it
+    pub fn summary(mut self, summary: Option<ListingSummary>) -> Self {
+        self.summary = summary;
+        self
+    }
+}
+
+impl Renderer for NdjsonRenderer {
+    fn render(&self, entries: &[FileEntry], out: &mut dyn WriteColor) -> io::Result<()> {
+        let mut summary = self.summary.clone();
+        for entry in entries {
+            match write_ndjson_entry(out, entry) {
+                Ok(()) => {
+                    if let Some(summary) = &mut summary {
+                        summary.add_entry(entry);
+                    }
+                }
+                // One bad entry is reported and skipped instead of ending the stream
+                Err(e) if e.kind() == io::ErrorKind::InvalidData => match &mut summary {
+                    Some(summary) => summary.add_error(WalkError::new(entry.path(), e)),
+                    None => eprintln!("Warning: Cannot serialize {:?}: {}", entry.path(), e),
+                },
+                Err(e) => return Err(e),
+            }
+        }
+        if let Some(summary) = &summary {
+            write_ndjson_summary(out, summary)?;
+        }
+        Ok(())
+    }
+}
+
+/// CSV or TSV with a header row
+pub struct DelimitedRenderer<'a> {
+    columns: &'a [Column],
+    separator: &'static str,
+    terminator: &'static str,
+    escape: fn(&str) -> Cow<'_, str>,
+}
+
+impl<'a> DelimitedRenderer<'a> {
+    /// CSV as in RFC 4180: fields quoted when needed and records ending in CRLF
+    pub fn csv(columns: &'a [Column]) -> Self {
+        Self {
+            columns,
+            separator: ",",
+            terminator: "\r\n",
+            escape: csv_field,
+        }
+    }
+
+    /// Tab-separated values. Tabs, newlines and backslashes inside fields are
+    /// written as `\t`, `\n` and `\\`.
+    pub fn tsv(columns: &'a [Column]) -> Self {
+        Self {
+            columns,
+            separator: "\t",
+            terminator: "\n",
+            escape: tsv_field,
+        }
+    }
+}
+
+impl Renderer for DelimitedRenderer<'_> {
+    fn render(&self, entries: &[FileEntry], out: &mut dyn WriteColor) -> io::Result<()> {
+        let escape = self.escape;
+        let header: Vec<_> = self.columns.iter().map(|c| escape(c.header())).collect();
+        write!(out, "{}{}", header.join(self.separator), self.terminator)?;
+
+        for entry in entries {
+            let row: Vec<String> = self
+                .columns
+                .iter()
+                .map(|c| escape(&c.raw_value(entry)).into_owned())
+                .collect();
+            write!(out, "{}{}", row.join(self.separator), self.terminator)?;
+        }
+        Ok(())
+    }
+}
+
+/// Quote a CSV field if it contains a separator, quote or line break
+fn csv_field(value: &str) -> Cow<'_, str> {
+    if value.contains([',', '"', '\n', '\r']) {
+        Cow::Owned(format!("\"{}\"", value.replace('"', "\"\"")))
+    } else {
+        Cow::Borrowed(value)
+    }
+}
+
+/// Escape characters that would break a TSV row
+fn tsv_field(value: &str) -> Cow<'_, str> {
+    if value.contains(['\t', '\n', '\r', '\\']) {
+        Cow::Owned(
+            value
+                .replace('\\', "\\\\")
+                .replace('\t', "\\t")
+                .replace('\n', "\\n")
+                .replace('\r', "\\r"),
+        )
+    } else {
+        Cow::Borrowed(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ffi::OsStr;
+    use std::os::unix::ffi::OsStrExt;
+    use std::path::PathBuf;
+    use termcolor::NoColor;
+
+    fn render(renderer: &dyn Renderer, entries: &[FileEntry]) -> String {
+        let mut out = NoColor::new(Vec::new());
+        renderer.render(entries, &mut out).unwrap();
+        String::from_utf8(out.into_inner()).unwrap()
+    }
+
+    fn entries() -> Vec<FileEntry> {
+        vec![
+            FileEntry::new(PathBuf::from("root/a,b.txt"), false, 12, None, None),
+            FileEntry::new(PathBuf::from("root/sub"), true, 4096, None, None),
+        ]
+    }
+
+    #[test]
+    fn test_lines_renderer() {
+        let root = PathBuf::from("root");
+        assert_eq!(
+            render(&LinesRenderer::new(&root), &entries()),
+            "a,b.txt\nsub\n"
+        );
+        assert_eq!(
+            render(&LinesRenderer::new(&root).null_terminated(true), &entries()),
+            "a,b.txt\0sub\0"
+        );
+
+        let bad = vec![FileEntry::new(
+            PathBuf::from(OsStr::from_bytes(b"root/bad\xff")),
+            false,
+            0,
+            None,
+            None,
+        )];
+        let mut out = NoColor::new(Vec::new());
+        LinesRenderer::new(&root)
+            .null_terminated(true)
+            .render(&bad, &mut out)
+            .unwrap();
+        assert_eq!(out.into_inner(), b"bad\xff\0");
+        let mut out = NoColor::new(Vec::new());
+        LinesRenderer::new(&root)
+            .quoting(QuotingStyle::C)
+            .render(&bad, &mut out)
+            .unwrap();
+        assert_eq!(out.into_inner(), b"\"bad\\377\"\n");
+    }
+
+    #[test]
+    fn test_ndjson_renderer_writes_whole_records() {
+        let mut entries = entries();
+        entries.push(FileEntry::new(
+            PathBuf::from(OsStr::from_bytes(b"root/bad\xff")),
+            false,
+            3,
+            None,
+            None,
+        ));
+        let renderer = NdjsonRenderer::new().summary(Some(ListingSummary::default()));
+        let records: Vec<serde_json::Value> = render(&renderer, &entries)
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+
+        assert_eq!(records.len(), 4);
+        assert_eq!(records[2]["path"], "root/bad\u{fffd}");
+        assert_eq!(records[3]["summary"]["files"], 2);
+        assert_eq!(records[3]["summary"]["errors"], serde_json::json!([]));
+    }
+
+    #[test]
+    fn test_delimited_renderer() {
+        let columns = [Column::Name, Column::Size, Column::Type];
+        assert_eq!(
+            render(&DelimitedRenderer::csv(&columns), &entries()),
+            "name,size,type\r\n\"a,b.txt\",12,file\r\nsub,4096,directory\r\n"
+        );
+    }
+
+    #[test]
+    fn test_long_renderer() {
+        let columns = [Column::Size, Column::Name];
+        let theme = ColorTheme::new(false);
+        assert_eq!(
+            render(&LongRenderer::new(&columns, &theme), &entries()),
+            " Size Name\n ------------\n   12 a,b.txt\n 4096 sub\n"
+        );
+    }
+
+    #[test]
+    fn test_field_list() {
+        assert_eq!(
+            Field::list(&[Column::Size, Column::Name], true),
+            vec![
+                Field::Column(Column::Size),
+                Field::Bar,
+                Field::Percent,
+                Field::Column(Column::Name)
+            ]
+        );
+        assert_eq!(
+            Field::list(&[Column::Name, Column::Size], true)[1..],
+            [Field::Column(Column::Size), Field::Bar, Field::Percent]
+        );
+    }
+
+    #[test]
+    fn test_long_renderer_bars() {
+        let entries = vec![
+            FileEntry::new(PathBuf::from("root/sub"), true, 4096, None, None),
+            FileEntry::new(PathBuf::from("root/sub/x"), false, 30, None, None),
+            FileEntry::new(PathBuf::from("root/a"), false, 10, None, None),
+        ];
+        let columns = [Column::Size, Column::Name];
+        let theme = ColorTheme::new(false);
+        let output = render(&LongRenderer::new(&columns, &theme).bars(true), &entries);
+        let lines: Vec<&str> = output.lines().collect();
+
+        assert!(lines[0].starts_with(" Size Usage"));
+        assert!(lines[0].ends_with(" % Name"));
+        // The directory counts the file below it and is 75% of the listing
+        assert!(lines[2].starts_with("   30 ███"));
+        assert!(lines[2].ends_with(" 75.0% sub"));
+        assert!(lines[3].ends_with("100.0% x"));
+        assert!(lines[4].ends_with(" 25.0% a"));
+    }
+
+    #[test]
+    fn test_tree_renderer_bars_without_contents() {
+        // Without --recursive the directory's contents are not listed
+        let entries = vec![
+            FileEntry::new(PathBuf::from("root/d"), true, 3072, None, None),
+            FileEntry::new(PathBuf::from("root/a"), false, 1024, None, None),
+        ];
+        let theme = ColorTheme::new(false);
+        let output = render(
+            &TreeRenderer::new(&theme)
+                .bars(true)
+                .size_format(Some(SizeFormat::default())),
+            &entries,
+        );
+        let lines: Vec<&str> = output.lines().collect();
+
+        assert!(lines[0].starts_with(" 75.0% "));
+        assert!(lines[0].ends_with("├── d (3.0 KiB)"));
+        assert!(lines[1].starts_with(" 25.0% "));
+        assert!(lines[1].ends_with("└── a (1.0 KiB)"));
+    }
+
+    #[test]
+    fn test_tree_renderer_totals_without_contents() {
+        let entries = vec![
+            FileEntry::new(PathBuf::from("root/d"), true, 3072, None, None),
+            FileEntry::new(PathBuf::from("root/e"), true, 2048, None, None),
+            FileEntry::new(PathBuf::from("root/a"), false, 1024, None, None),
+        ];
+        let theme = ColorTheme::new(false);
+        assert_eq!(
+            render(
+                &TreeRenderer::new(&theme).dir_totals(true).collapse(Some(1)),
+                &entries
+            ),
+            "├── d (3.0 KiB)\n└── … 2 more entries (3.0 KiB)\n"
+        );
+    }
+
+    #[test]
+    fn test_group_thousands() {
+        assert_eq!(group_thousands(0), "0");
+        assert_eq!(group_thousands(999), "999");
+        assert_eq!(group_thousands(1204), "1,204");
+        assert_eq!(group_thousands(1234567), "1,234,567");
+    }
+
+    #[test]
+    fn test_tree_renderer() {
+        let entries = vec![
+            FileEntry::new(PathBuf::from("root/b.txt"), false, 2048, None, None),
+            FileEntry::new(PathBuf::from("root/c.txt"), false, 1024, None, None),
+            FileEntry::new(PathBuf::from("root/d.txt"), false, 1024, None, None),
+            FileEntry::new(PathBuf::from("root/sub"), true, 4096, None, None),
+            FileEntry::new(PathBuf::from("root/sub/x"), false, 10, None, None),
+        ];
+        let theme = ColorTheme::new(false);
+        assert_eq!(
+            render(&TreeRenderer::new(&theme), &entries),
+            "├── b.txt\n├── c.txt\n├── d.txt\n└── sub\n    └── x\n"
+        );
+        assert_eq!(
+            render(
+                &TreeRenderer::new(&theme)
+                    .dir_totals(true)
+                    .collapse(Some(2))
+                    .dirs_first(true)
+                    .charset(TreeCharset::Ascii),
+                &entries
+            ),
+            "|-- sub (10 B, 1 file)\n|   `-- x\n|-- b.txt\n`-- ... 2 more files (2.0 KiB)\n"
+        );
+    }
+
+    #[test]
+    fn test_markdown_escape() {
+        assert_eq!(markdown_escape("plain.txt"), "plain.txt");
+        assert_eq!(markdown_escape("a|b`c`.md"), "a\\|b\\`c\\`.md");
+        assert_eq!(markdown_escape("two\nlines"), "two lines");
+    }
+
+    #[test]
+    fn test_markdown_table() {
+        let columns = [Column::Name, Column::Size];
+        let theme = ColorTheme::new(true);
+        let mut entries = entries();
+        entries.push(FileEntry::new(
+            PathBuf::from("root/x|y.md"),
+            false,
+            5,
+            None,
+            None,
+        ));
+        assert_eq!(
+            render(
+                &TableRenderer::new(&columns, &theme).markdown(true),
+                &entries
+            ),
+            "| Name    | Size |\n\
+             |---------|------|\n\
+             | a,b.txt |   12 |\n\
+             | sub     | 4096 |\n\
+             | x\\|y.md |    5 |\n"
+        );
+    }
+
+    #[test]
+    fn test_markdown_tree() {
+        let entries = vec![
+            FileEntry::new(PathBuf::from("root/src"), true, 4096, None, None),
+            FileEntry::new(PathBuf::from("root/src/main.rs"), false, 300, None, None),
+            FileEntry::new(PathBuf::from("root/README.md"), false, 10, None, None),
+        ];
+        assert_eq!(
+            render(&MarkdownTreeRenderer::new(), &entries),
+            "- **src/**\n  - main.rs\n- README.md\n"
+        );
+    }
+
+    /// Fails every write as if the reader had closed the pipe
+    struct ClosedPipe;
+
+    impl Write for ClosedPipe {
+        fn write(&mut self, _: &[u8]) -> io::Result<usize> {
+            Err(io::ErrorKind::BrokenPipe.into())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_write_errors_are_returned() {
+        let theme = ColorTheme::new(false);
+        let renderers: [Box<dyn Renderer>; 3] = [
+            Box::new(GridRenderer::new(&theme)),
+            Box::new(TreeRenderer::new(&theme)),
+            Box::new(NdjsonRenderer::new()),
+        ];
+        for renderer in renderers {
+            let error = renderer
+                .render(&entries(), &mut NoColor::new(ClosedPipe))
+                .unwrap_err();
+            assert_eq!(error.kind(), io::ErrorKind::BrokenPipe);
+        }
+    }
+
+    #[test]
+    fn test_csv_field() {
+        assert_eq!(csv_field("plain.txt"), "plain.txt");
+        assert_eq!(csv_field("a,b"), "\"a,b\"");
+        assert_eq!(csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+        assert_eq!(csv_field("two\nlines"), "\"two\nlines\"");
+    }
+
+    #[test]
+    fn test_tsv_field() {
+        assert_eq!(tsv_field("plain.txt"), "plain.txt");
+        assert_eq!(tsv_field("a\tb\nc\\d"), "a\\tb\\nc\\\\d");
+    }
+
+    #[test]
+    fn test_grid_layout_down() {
+        // 5 items of width 3 in 16 columns: "aaa  bbb  ccc" fits 3 columns,
+        // which down-then-across turns into 2 rows of 3 columns
+        let layout = GridLayout::new(&[3, 3, 3, 3, 3], 16, false);
+        assert_eq!(layout.rows, 2);
+        assert_eq!(layout.column_widths, vec![3, 3, 3]);
+        assert_eq!(layout.index(0, 1, false, 5), Some(2));
+        assert_eq!(layout.index(1, 2, false, 5), None);
+    }
+
+    #[test]
+    fn test_grid_layout_across() {
+        let layout = GridLayout::new(&[1, 5, 1, 1], 9, true);
+        assert_eq!(layout.rows, 2);
+        assert_eq!(layout.column_widths, vec![1, 5]);
+        assert_eq!(layout.index(1, 0, true, 4), Some(2));
+    }
+
+    #[test]
+    fn test_grid_layout_narrow_and_empty() {
+        let layout = GridLayout::new(&[50, 60], 40, false);
+        assert_eq!(layout.rows, 2);
+        assert_eq!(layout.column_widths, vec![60]);
+
+        let layout = GridLayout::new(&[], 80, false);
+        assert_eq!(layout.rows, 0);
+    }
+
+    #[test]
+    fn test_wide_characters() {
+        assert_eq!("日本語.txt".width(), 10);
+        assert_eq!("🦀.rs".width(), 5);
+    }
+}
@@ -0,0 +1,252 @@
+use std::borrow::Cow;
+use std::ffi::OsStr;
+use std::io::Write;
+use std::os::unix::ffi::OsStrExt;
+
+/// How names are quoted, following GNU `ls --quoting-style`
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum QuotingStyle {
+    /// Print names as they are
+    #[default]
+    Literal,
+    /// Single-quote names containing shell metacharacters, or double-quote them
+    /// when they contain a single quote and nothing special inside double quotes
+    Shell,
+    /// Like `Shell`, but write control characters as `$'\n'` escapes
+    ShellEscape,
+    /// Double-quote names with C escapes
+    C,
+}
+
+impl QuotingStyle {
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s.to_lowercase().as_str() {
+            "literal" => Some(QuotingStyle::Literal),
+            "shell" => Some(QuotingStyle::Shell),
+            "shell-escape" => Some(QuotingStyle::ShellEscape),
+            "c" => Some(QuotingStyle::C),
+            _ => None,
+        }
+    }
+
+    /// Quote a name given as raw bytes. Bytes that are not valid UTF-8 are kept as
+    /// they are by `Literal` and `Shell`, and written as `\ooo` escapes by
+    /// `ShellEscape` and `C`.
+    pub fn quote<'a>(&self, name: &'a OsStr) -> Cow<'a, [u8]> {
+        let name = name.as_bytes();
+        match self {
+            QuotingStyle::Literal => Cow::Borrowed(name),
+            QuotingStyle::Shell => shell_quote(name, false),
+            QuotingStyle::ShellEscape => shell_quote(name, true),
+            QuotingStyle::C => Cow::Owned(c_quote(name)),
+        }
+    }
+}
+
+/// A character of a name, or a byte that is not part of valid UTF-8
+#[derive(Debug, Clone, Copy)]
+enum Unit {
+    Char(char),
+    Byte(u8),
+}
+
+fn units(name: &[u8]) -> impl Iterator<Item = Unit> + '_ {
+    name.utf8_chunks().flat_map(|chunk| {
+        chunk
+            .valid()
+            .chars()
+            .map(Unit::Char)
+            .chain(chunk.invalid().iter().map(|&b| Unit::Byte(b)))
+    })
+}
+
+/// Characters that never need quoting for the shell; printable non-ASCII is kept as is
+fn is_shell_safe(c: char) -> bool {
+    c.is_ascii_alphanumeric() || "-_./:+,@%=^".contains(c) || (!c.is_ascii() && !c.is_control())
+}
+
+fn needs_shell_quoting(name: &[u8]) -> bool {
+    name.is_empty()
+        || name.starts_with(b"~")
+        || name.starts_with(b"#")
+        || !units(name).all(|unit| match unit {
+            Unit::Char(c) => is_shell_safe(c) || c == '~' || c == '#',
+            Unit::Byte(_) => false,
+        })
+}
+
+/// Whether a name with a single quote can be double-quoted as it is, as GNU ls
+/// prints `"it's"` rather than `'it'\''s'`. `!` is left out since bash expands
+/// history inside double quotes.
+fn is_double_quotable(name: &[u8]) -> bool {
+    name.contains(&b'\'')
+        && units(name).all(|unit| match unit {
+            Unit::Char(c) => !c.is_control() && !"$`\\\"!".contains(c),
+            Unit::Byte(_) => false,
+        })
+}
+
+fn shell_quote(name: &[u8], escape_controls: bool) -> Cow<'_, [u8]> {
+    if !needs_shell_quoting(name) {
+        return Cow::Borrowed(name);
+    }
+    if name.is_empty() {
+        return Cow::Borrowed(b"''");
+    }
+    if is_double_quotable(name) {
+        let mut quoted = Vec::with_capacity(name.len() + 2);
+        quoted.push(b'"');
+        quoted.extend_from_slice(name);
+        quoted.push(b'"');
+        return Cow::Owned(quoted);
+    }
+
+    let mut quoted = Vec::with_capacity(name.len() + 2);
+    let mut in_quotes = false;
+
+    for unit in units(name) {
+        let is_control = match unit {
+            Unit::Char('\'') => {
+                // Close the quotes, add an escaped quote
+                if in_quotes {
+                    quoted.push(b'\'');
+                    in_quotes = false;
+                }
+                quoted.extend_from_slice(b"\\'");
+                continue;
+            }
+            Unit::Char(c) => c.is_control(),
+            Unit::Byte(_) => true,
+        };
+        if escape_controls && is_control {
+            if in_quotes {
+                quoted.push(b'\'');
+                in_quotes = false;
+            }
+            quoted.extend_from_slice(b"$'");
+            push_c_escape(&mut quoted, unit);
+            quoted.push(b'\'');
+        } else {
+            if !in_quotes {
+                quoted.push(b'\'');
+                in_quotes = true;
+            }
+            push_unit(&mut quoted, unit);
+        }
+    }
+
+    if in_quotes {
+        quoted.push(b'\'');
+    }
+    Cow::Owned(quoted)
+}
+
+fn c_quote(name: &[u8]) -> Vec<u8> {
+    let mut quoted = Vec::with_capacity(name.len() + 2);
+    quoted.push(b'"');
+    for unit in units(name) {
+        match unit {
+            Unit::Char('"') => quoted.extend_from_slice(b"\\\""),
+            Unit::Char('\\') => quoted.extend_from_slice(b"\\\\"),
+            Unit::Char(c) if c.is_control() => push_c_escape(&mut quoted, unit),
+            Unit::Byte(_) => push_c_escape(&mut quoted, unit),
+            Unit::Char(_) => push_unit(&mut quoted, unit),
+        }
+    }
+    quoted.push(b'"');
+    quoted
+}
+
+/// Append a character or raw byte unchanged
+fn push_unit(out: &mut Vec<u8>, unit: Unit) {
+    match unit {
+        Unit::Char(c) => out.extend_from_slice(c.encode_utf8(&mut [0u8; 4]).as_bytes()),
+        Unit::Byte(b) => out.push(b),
+    }
+}
+
+/// Append a C-style escape for a control character or a raw byte
+fn push_c_escape(out: &mut Vec<u8>, unit: Unit) {
+    let c = match unit {
+        Unit::Char(c) => c,
+        Unit::Byte(b) => {
+            let _ = write!(out, "\\{:03o}", b);
+            return;
+        }
+    };
+    match c {
+        '\n' => out.extend_from_slice(b"\\n"),
+        '\t' => out.extend_from_slice(b"\\t"),
+        '\r' => out.extend_from_slice(b"\\r"),
+        '\x07' => out.extend_from_slice(b"\\a"),
+        '\x08' => out.extend_from_slice(b"\\b"),
+        '\x0b' => out.extend_from_slice(b"\\v"),
+        '\x0c' => out.extend_from_slice(b"\\f"),
+        '\x1b' => out.extend_from_slice(b"\\033"),
+        c => {
+            for byte in c.encode_utf8(&mut [0u8; 4]).bytes() {
+                let _ = write!(out, "\\{:03o}", byte);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn quote(style: QuotingStyle, name: &str) -> String {
+        String::from_utf8(style.quote(OsStr::new(name)).into_owned()).unwrap()
+    }
+
+    #[test]
+    fn test_literal() {
+        assert_eq!(quote(QuotingStyle::Literal, "a\nb"), "a\nb");
+    }
+
+    #[test]
+    fn test_shell() {
+        let shell = QuotingStyle::Shell;
+        assert_eq!(quote(shell, "plain-name_1.txt"), "plain-name_1.txt");
+        assert_eq!(quote(shell, "with space"), "'with space'");
+        assert_eq!(quote(shell, "it's"), "\"it's\"");
+        assert_eq!(quote(shell, "it's $5"), "'it'\\''s $5'");
+        assert_eq!(quote(shell, "it's \"so\""), "'it'\\''s \"so\"'");
+        assert_eq!(quote(shell, "a!'b"), "'a!'\\''b'");
+        assert_eq!(quote(shell, "~home"), "'~home'");
+        assert_eq!(quote(shell, ""), "''");
+        assert_eq!(quote(shell, "a\nb"), "'a\nb'");
+        assert_eq!(quote(shell, "日本語🦀.rs"), "日本語🦀.rs");
+    }
+
+    #[test]
+    fn test_shell_escape() {
+        let shell = QuotingStyle::ShellEscape;
+        assert_eq!(quote(shell, "a\nb"), "'a'$'\\n''b'");
+        assert_eq!(quote(shell, "tab\t"), "'tab'$'\\t'");
+        assert_eq!(quote(shell, "ok"), "ok");
+        assert_eq!(quote(shell, "it's"), "\"it's\"");
+        assert_eq!(quote(shell, "it's\n"), "'it'\\''s'$'\\n'");
+    }
+
+    #[test]
+    fn test_c() {
+        let c = QuotingStyle::C;
+        assert_eq!(quote(c, "plain"), "\"plain\"");
+        assert_eq!(quote(c, "say \"hi\"\n"), "\"say \\\"hi\\\"\\n\"");
+        assert_eq!(quote(c, "\x01"), "\"\\001\"");
+    }
+
+    #[test]
+    fn test_non_utf8_names() {
+        let name = OsStr::from_bytes(b"bad\xff");
+        assert_eq!(QuotingStyle::Literal.quote(name).as_ref(), b"bad\xff");
+        assert_eq!(QuotingStyle::Shell.quote(name).as_ref(), b"'bad\xff'");
+        assert_eq!(
+            QuotingStyle::ShellEscape.quote(name).as_ref(),
+            b"'bad'$'\\377'"
+        );
+        assert_eq!(QuotingStyle::C.quote(name).as_ref(), b"\"bad\\377\"");
+    }
+}
@@ -0,0 +1,188 @@
+use crate::file_entry::FileEntry;
+use chrono::{DateTime, Local, SecondsFormat};
+use serde::{Serialize, Serializer};
+use std::path::{Path, PathBuf};
+
+/// Version of the JSON output schema. Adding optional fields keeps the version;
+/// renaming or removing a field or changing its type bumps it.
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// JSON Schema describing `--format json`, `json-tree` and `ndjson` output
+pub const JSON_SCHEMA: &str = include_str!("../schema/ptlist-output-v1.json");
+
+/// Serialize a path as a string, replacing bytes that are not valid UTF-8 with
+/// U+FFFD so that one odd file name cannot fail a whole listing
+pub(crate) fn serialize_path_lossy<P: AsRef<Path>, S: Serializer>(
+    path: &P,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&path.as_ref().to_string_lossy())
+}
+
+/// A point in time as nanoseconds since the Unix epoch and as an RFC 3339 string
+#[derive(Debug, Serialize)]
+pub struct Timestamp {
+    epoch_ns: i64,
+    rfc3339: String,
+}
+
+impl From<DateTime<Local>> for Timestamp {
+    fn from(time: DateTime<Local>) -> Self {
+        Self {
+            epoch_ns: time
+                .timestamp_nanos_opt()
+                .unwrap_or_else(|| time.timestamp().saturating_mul(1_000_000_000)),
+            rfc3339: time.to_rfc3339_opts(SecondsFormat::AutoSi, false),
+        }
+    }
+}
+
+/// Permission bits in octal and symbolic form
+#[derive(Debug, Serialize)]
+pub struct Mode {
+    octal: String,
+    symbolic: String,
+}
+
+/// The serialized form of a [`FileEntry`]
+#[derive(Debug, Serialize)]
+struct EntryRecord<'a> {
+    name: &'a str,
+    #[serde(serialize_with = "serialize_path_lossy")]
+    path: &'a Path,
+    kind: &'static str,
+    size: u64,
+    mode: Mode,
+    uid: u32,
+    gid: u32,
+    owner: Option<&'a str>,
+    group: Option<&'a str>,
+    inode: u64,
+    links: u64,
+    disk_usage: u64,
+    depth: usize,
+    modified: Timestamp,
+    accessed: Option<Timestamp>,
+    changed: Option<Timestamp>,
+    created: Option<Timestamp>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    match_count: Option<usize>,
+}
+
+impl Serialize for FileEntry {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        EntryRecord {
+            name: self.name(),
+            path: self.path(),
+            kind: self.kind(),
+            size: self.size(),
+            mode: Mode {
+                octal: format!("{:04o}", self.mode() & 0o7777),
+                symbolic: self.permissions(),
+            },
+            uid: self.uid(),
+            gid: self.gid(),
+            owner: self.owner(),
+            group: self.group(),
+            inode: self.inode(),
+            links: self.links(),
+            disk_usage: self.disk_usage(),
+            depth: self.depth(),
+            modified: self.modified().into(),
+            accessed: self.accessed().map(Timestamp::from),
+            changed: self.changed().map(Timestamp::from),
+            created: self.created().map(Timestamp::from),
+            match_count: self.match_count(),
+        }
+        .serialize(serializer)
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct Tool {
+    name: &'static str,
+    version: &'static str,
+}
+
+/// Top-level fields wrapped around `--format json` and `json-tree` output
+#[derive(Debug, Clone, Serialize)]
+pub struct Envelope {
+    schema_version: u32,
+    tool: Tool,
+    #[serde(serialize_with = "serialize_path_lossy")]
+    root: PathBuf,
+    options: serde_json::Value,
+}
+
+impl Envelope {
+    /// Describe a listing of `root` produced with the given options
+    pub fn new(root: &Path, options: &impl Serialize) -> serde_json::Result<Self> {
+        Ok(Self {
+            schema_version: SCHEMA_VERSION,
+            tool: Tool {
+                name: env!("CARGO_PKG_NAME"),
+                version: env!("CARGO_PKG_VERSION"),
+            },
+            root: root.to_path_buf(),
+            options: serde_json::to_value(options)?,
+        })
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_entry_record() {
+        let modified = Local.timestamp_opt(1_700_000_000, 5).unwrap();
+        let entry = FileEntry::new(
+            PathBuf::from("dir/main.rs"),
+            false,
+            42,
+            Some(modified),
+            None,
+        )
+        .with_mode(0o100755);
+        let value = serde_json::to_value(&entry).unwrap();
+
+        assert_eq!(value["kind"], "file");
+        assert_eq!(value["mode"]["octal"], "0755");
+        assert_eq!(value["mode"]["symbolic"], "-rwxr-xr-x");
+        assert_eq!(value["modified"]["epoch_ns"], 1_700_000_000_000_000_005i64);
+        assert!(
+            DateTime::parse_from_rfc3339(value["modified"]["rfc3339"].as_str().unwrap()).is_ok()
+        );
+        assert!(value["created"].is_null());
+        assert!(value.get("match_count").is_none());
+    }
+
+    #[test]
+    fn test_non_utf8_path_is_lossy() {
+        use std::ffi::OsStr;
+        use std::os::unix::ffi::OsStrExt;
+
+        let path = PathBuf::from(OsStr::from_bytes(b"dir/bad\xff"));
+        let entry = FileEntry::new(path.clone(), false, 1, None, None);
+        let value = serde_json::to_value(&entry).unwrap();
+        assert_eq!(value["path"], "dir/bad\u{fffd}");
+
+        let envelope = Envelope::new(&path, &()).unwrap();
+        let value = serde_json::to_value(&envelope).unwrap();
+        assert_eq!(value["root"], "dir/bad\u{fffd}");
+    }
+
+    #[test]
+    fn test_schema_is_valid_json() {
+        let schema: serde_json::Value = serde_json::from_str(JSON_SCHEMA).unwrap();
+        assert_eq!(
+            schema["properties"]["schema_version"]["const"],
+            SCHEMA_VERSION
+        );
+    }
+}
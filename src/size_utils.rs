@@ -1,6 +1,51 @@
 use std::fmt;
 
-const UNITS: [&str; 6] = ["B", "KB", "MB", "GB", "TB", "PB"];
+const IEC_UNITS: [&str; 6] = ["B", "KiB", "MiB", "GiB", "TiB", "PiB"];
+const SI_UNITS: [&str; 6] = ["B", "kB", "MB", "GB", "TB", "PB"];
+
+/// How human-readable sizes are scaled and labelled
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SizeFormat {
+    /// Use powers of 1000 (`kB`, `MB`) instead of powers of 1024 (`KiB`, `MiB`)
+    pub si: bool,
+    /// Number of decimal places for scaled values
+    pub precision: usize,
+}
+
+impl SizeFormat {
+    pub fn new(si: bool, precision: usize) -> Self {
+        Self { si, precision }
+    }
+
+    /// Format a byte count using this format
+    pub fn format(&self, bytes: u64) -> String {
+        let (base, units) = if self.si {
+            (1000.0, &SI_UNITS)
+        } else {
+            (1024.0, &IEC_UNITS)
+        };
+
+        let mut size = bytes as f64;
+        let mut unit_index = 0;
+
+        while size >= base && unit_index < units.len() - 1 {
+            size /= base;
+            unit_index += 1;
+        }
+
+        if unit_index == 0 {
+            format!("{} {}", bytes, units[unit_index])
+        } else {
+            format!("{:.*} {}", self.precision, size, units[unit_index])
+        }
+    }
+}
+
+impl Default for SizeFormat {
+    fn default() -> Self {
+        Self::new(false, 1)
+    }
+}
 
 /// A wrapper type for file sizes that provides human-readable formatting
 #[derive(Debug, Clone, Copy)]
@@ -14,19 +59,7 @@ impl HumanSize {
 
     /// Get the size in a human-readable format
     pub fn format(&self) -> String {
-        let mut size = self.0 as f64;
-        let mut unit_index = 0;
-
-        while size >= 1024.0 && unit_index < UNITS.len() - 1 {
-            size /= 1024.0;
-            unit_index += 1;
-        }
-
-        if unit_index == 0 {
-            format!("{} {}", size as u64, UNITS[unit_index])
-        } else {
-            format!("{:.1} {}", size, UNITS[unit_index])
-        }
+        SizeFormat::default().format(self.0)
     }
 }
 
@@ -36,6 +69,46 @@ impl fmt::Display for HumanSize {
     }
 }
 
+/// Parse a size argument such as `1048576`, `10K`, `1.5MiB`, `2G` or `500kB`
+///
+/// Suffixes follow GNU coreutils: `K`, `M`, `G`... and `KiB`, `MiB`, `GiB`... are
+/// powers of 1024, while `kB`, `MB`, `GB`... are powers of 1000.
+pub fn parse_size(s: &str) -> Result<u64, String> {
+    let s = s.trim();
+    let split = s
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .unwrap_or(s.len());
+    let (number, suffix) = s.split_at(split);
+    let suffix = suffix.trim();
+
+    let value: f64 = number.parse().map_err(|_| format!("invalid size: {}", s))?;
+
+    let (prefix, base) = if let Some(prefix) = suffix.strip_suffix("iB") {
+        (prefix, 1024u64)
+    } else if let Some(prefix) = suffix.strip_suffix('B').filter(|p| !p.is_empty()) {
+        (prefix, 1000u64)
+    } else {
+        (suffix, 1024u64)
+    };
+
+    let exponent = match prefix.to_ascii_uppercase().as_str() {
+        "" | "B" => 0,
+        "K" => 1,
+        "M" => 2,
+        "G" => 3,
+        "T" => 4,
+        "P" => 5,
+        "E" => 6,
+        _ => return Err(format!("invalid size unit '{}' in {}", suffix, s)),
+    };
+
+    let bytes = value * (base as f64).powi(exponent);
+    if !bytes.is_finite() || bytes > u64::MAX as f64 {
+        return Err(format!("size out of range: {}", s));
+    }
+    Ok(bytes.round() as u64)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -44,9 +117,36 @@ mod tests {
     fn test_human_size_format() {
         assert_eq!(HumanSize(0).format(), "0 B");
         assert_eq!(HumanSize(1023).format(), "1023 B");
-        assert_eq!(HumanSize(1024).format(), "1.0 KB");
-        assert_eq!(HumanSize(1024 * 1024).format(), "1.0 MB");
-        assert_eq!(HumanSize(1024 * 1024 * 1024).format(), "1.0 GB");
-        assert_eq!(HumanSize(1024 * 1024 * 1024 * 1024).format(), "1.0 TB");
+        assert_eq!(HumanSize(1024).format(), "1.0 KiB");
+        assert_eq!(HumanSize(1024 * 1024).format(), "1.0 MiB");
+        assert_eq!(HumanSize(1024 * 1024 * 1024).format(), "1.0 GiB");
+        assert_eq!(HumanSize(1024 * 1024 * 1024 * 1024).format(), "1.0 TiB");
+    }
+
+    #[test]
+    fn test_size_format_si_and_precision() {
+        let si = SizeFormat::new(true, 1);
+        assert_eq!(si.format(999), "999 B");
+        assert_eq!(si.format(1000), "1.0 kB");
+        assert_eq!(si.format(1_500_000), "1.5 MB");
+
+        let precise = SizeFormat::new(false, 2);
+        assert_eq!(precise.format(1536), "1.50 KiB");
+        assert_eq!(SizeFormat::new(false, 0).format(1536), "2 KiB");
+    }
+
+    #[test]
+    fn test_parse_size() {
+        assert_eq!(parse_size("1048576"), Ok(1048576));
+        assert_eq!(parse_size("10K"), Ok(10 * 1024));
+        assert_eq!(parse_size("10k"), Ok(10 * 1024));
+        assert_eq!(parse_size("1.5MiB"), Ok(1572864));
+        assert_eq!(parse_size("2G"), Ok(2 * 1024 * 1024 * 1024));
+        assert_eq!(parse_size("500kB"), Ok(500_000));
+        assert_eq!(parse_size("3 MB"), Ok(3_000_000));
+        assert_eq!(parse_size("12B"), Ok(12));
+        assert!(parse_size("").is_err());
+        assert!(parse_size("10X").is_err());
+        assert!(parse_size("1.2.3K").is_err());
     }
 }
@@ -1,5 +1,8 @@
+use crate::collation::Collation;
 use crate::file_entry::FileEntry;
 use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
 
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum SortField {
@@ -7,7 +10,19 @@ pub enum SortField {
     Size,
     Modified,
     Created,
+    /// File kind (`directory`, `file`, `symlink`, ...) in alphabetical order
     Type,
+    Extension,
+    Owner,
+    Group,
+    Permissions,
+    Depth,
+    FullPath,
+    Inode,
+    Accessed,
+    NameLength,
+    /// Keep directory order; skips sorting entirely when it is the only key
+    None,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -17,6 +32,7 @@ pub enum SortOrder {
 }
 
 impl SortField {
+    #[allow(clippy::should_implement_trait)]
     pub fn from_str(s: &str) -> Option<Self> {
         match s.to_lowercase().as_str() {
             "name" => Some(SortField::Name),
@@ -24,12 +40,75 @@ impl SortField {
             "modified" => Some(SortField::Modified),
             "created" => Some(SortField::Created),
             "type" => Some(SortField::Type),
+            "extension" | "ext" => Some(SortField::Extension),
+            "owner" | "user" => Some(SortField::Owner),
+            "group" => Some(SortField::Group),
+            "permissions" | "perms" | "mode" => Some(SortField::Permissions),
+            "depth" => Some(SortField::Depth),
+            "path" | "full-path" | "fullpath" => Some(SortField::FullPath),
+            "inode" => Some(SortField::Inode),
+            "accessed" | "atime" => Some(SortField::Accessed),
+            "name-length" | "namelength" | "length" => Some(SortField::NameLength),
+            "none" | "unsorted" => Some(SortField::None),
+            _ => None,
+        }
+    }
+}
+
+/// One key of a multi-key sort, e.g. `size:desc`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SortKey {
+    pub field: SortField,
+    pub order: SortOrder,
+}
+
+/// Where directories go relative to files, independent of the sort keys
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DirGrouping {
+    First,
+    Last,
+    None,
+}
+
+impl SortKey {
+    pub fn new(field: SortField, order: SortOrder) -> Self {
+        Self { field, order }
+    }
+
+    /// Parse a comma-separated key list such as `type,size:desc,name`.
+    /// Keys without an explicit direction use `default_order`.
+    /// On failure, returns the offending key.
+    pub fn parse_list(spec: &str, default_order: SortOrder) -> Result<Vec<Self>, String> {
+        spec.split(',')
+            .map(|key| {
+                let key = key.trim();
+                let (field, order) = match key.split_once(':') {
+                    Some((field, order)) => (field, SortOrder::from_str(order)),
+                    None => (key, Some(default_order)),
+                };
+                match (SortField::from_str(field), order) {
+                    (Some(field), Some(order)) => Ok(SortKey::new(field, order)),
+                    _ => Err(key.to_string()),
+                }
+            })
+            .collect()
+    }
+}
+
+impl DirGrouping {
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s.to_lowercase().as_str() {
+            "first" => Some(DirGrouping::First),
+            "last" => Some(DirGrouping::Last),
+            "none" => Some(DirGrouping::None),
             _ => None,
         }
     }
 }
 
 impl SortOrder {
+    #[allow(clippy::should_implement_trait)]
     pub fn from_str(s: &str) -> Option<Self> {
         match s.to_lowercase().as_str() {
             "asc" | "ascending" => Some(SortOrder::Ascending),
@@ -39,109 +118,419 @@ impl SortOrder {
     }
 }
 
-pub fn sort_entries(entries: &mut Vec<FileEntry>, field: SortField, order: SortOrder) {
-    entries.sort_by(|a, b| {
-        let cmp = match field {
-            SortField::Name => natural_sort(a.name(), b.name()),
-            SortField::Size => a.size().cmp(&b.size()),
-            SortField::Modified => a.modified().cmp(&b.modified()),
-            SortField::Created => a.created().cmp(&b.created()),
-            SortField::Type => {
-                // Sort directories first, then by extension
-                match (a.is_dir(), b.is_dir()) {
-                    (true, false) => Ordering::Less,
-                    (false, true) => Ordering::Greater,
-                    _ => {
-                        let a_ext = a.extension().unwrap_or_default();
-                        let b_ext = b.extension().unwrap_or_default();
-                        a_ext.cmp(b_ext)
-                    }
-                }
-            }
-        };
+/// Sorts entries by a chain of keys, each later key breaking ties of the previous ones.
+/// Remaining ties are broken by path so the result does not depend on directory order.
+#[derive(Debug, Clone)]
+pub struct Sorter {
+    keys: Vec<SortKey>,
+    group_dirs: DirGrouping,
+    collation: Collation,
+}
 
-        match order {
-            SortOrder::Ascending => cmp,
-            SortOrder::Descending => cmp.reverse(),
+impl Sorter {
+    pub fn new(keys: Vec<SortKey>) -> Self {
+        Self {
+            keys,
+            group_dirs: DirGrouping::None,
+            collation: Collation::default(),
         }
-    });
-}
+    }
 
-/// Natural sort comparison for strings containing numbers
-fn natural_sort(a: &str, b: &str) -> Ordering {
-    let mut a_chars = a.chars().peekable();
-    let mut b_chars = b.chars().peekable();
-
-    loop {
-        match (a_chars.peek(), b_chars.peek()) {
-            (None, None) => return Ordering::Equal,
-            (None, _) => return Ordering::Less,
-            (_, None) => return Ordering::Greater,
-            (Some(ac), Some(bc)) => {
-                if ac.is_digit(10) && bc.is_digit(10) {
-                    // Compare numbers
-                    let a_num = extract_number(&mut a_chars);
-                    let b_num = extract_number(&mut b_chars);
-                    match a_num.cmp(&b_num) {
-                        Ordering::Equal => continue,
-                        other => return other,
-                    }
+    pub fn group_dirs(mut self, group_dirs: DirGrouping) -> Self {
+        self.group_dirs = group_dirs;
+        self
+    }
+
+    /// Set how names are compared
+    pub fn collation(mut self, collation: Collation) -> Self {
+        self.collation = collation;
+        self
+    }
+
+    /// Replace the sort keys, keeping directory grouping and collation
+    pub fn with_keys(mut self, keys: Vec<SortKey>) -> Self {
+        self.keys = keys;
+        self
+    }
+
+    pub fn keys(&self) -> &[SortKey] {
+        &self.keys
+    }
+
+    pub fn compare(&self, a: &FileEntry, b: &FileEntry) -> Ordering {
+        let grouped = match self.group_dirs {
+            DirGrouping::First => b.is_dir().cmp(&a.is_dir()),
+            DirGrouping::Last => a.is_dir().cmp(&b.is_dir()),
+            DirGrouping::None => Ordering::Equal,
+        };
+
+        self.keys
+            .iter()
+            .fold(grouped, |acc, key| {
+                acc.then_with(|| match key.order {
+                    SortOrder::Ascending => self.compare_field(a, b, key.field),
+                    SortOrder::Descending => self.compare_field(a, b, key.field).reverse(),
+                })
+            })
+            .then_with(|| {
+                if self.keeps_directory_order() {
+                    Ordering::Equal
                 } else {
-                    // Compare characters
-                    match ac.cmp(bc) {
-                        Ordering::Equal => {
-                            a_chars.next();
-                            b_chars.next();
-                            continue;
+                    a.path().cmp(b.path())
+                }
+            })
+    }
+
+    pub fn sort(&self, entries: &mut [FileEntry]) {
+        if self.group_dirs == DirGrouping::None
+            && self.keys.iter().all(|k| k.field == SortField::None)
+        {
+            return;
+        }
+        entries.sort_by(|a, b| self.compare(a, b));
+    }
+
+    /// Sort siblings within each directory while keeping every directory
+    /// immediately followed by its (sorted) contents.
+    ///
+    /// Entries whose parent is not in the list are grouped by their parent
+    /// directory, and those groups are emitted in path order.
+    pub fn sort_hierarchical(&self, entries: &mut Vec<FileEntry>) {
+        let mut children: HashMap<&Path, Vec<usize>> = HashMap::new();
+        for (i, entry) in entries.iter().enumerate() {
+            let parent = entry.path().parent().unwrap_or(Path::new(""));
+            children.entry(parent).or_default().push(i);
+        }
+        for siblings in children.values_mut() {
+            siblings.sort_by(|&a, &b| self.compare(&entries[a], &entries[b]));
+        }
+
+        let paths: HashSet<&Path> = entries.iter().map(|e| e.path()).collect();
+        let mut top_level: Vec<&Path> = children
+            .keys()
+            .copied()
+            .filter(|parent| !paths.contains(parent))
+            .collect();
+        top_level.sort();
+
+        // Depth-first walk; the stack holds sibling lists still to be emitted
+        let mut order = Vec::with_capacity(entries.len());
+        for parent in top_level {
+            let mut stack = vec![children[parent].iter()];
+            while let Some(siblings) = stack.last_mut() {
+                match siblings.next() {
+                    Some(&i) => {
+                        order.push(i);
+                        if let Some(grandchildren) = children.get(entries[i].path()) {
+                            stack.push(grandchildren.iter());
                         }
-                        other => return other,
+                    }
+                    None => {
+                        stack.pop();
                     }
                 }
             }
         }
+
+        let mut slots: Vec<Option<FileEntry>> =
+            std::mem::take(entries).into_iter().map(Some).collect();
+        entries.extend(order.into_iter().filter_map(|i| slots[i].take()));
     }
-}
 
-fn extract_number<I>(chars: &mut std::iter::Peekable<I>) -> u64
-where
-    I: Iterator<Item = char>,
-{
-    let mut number = 0u64;
-    while let Some(c) = chars.peek() {
-        if c.is_digit(10) {
-            if let Some(digit) = c.to_digit(10) {
-                number = number * 10 + digit as u64;
-                chars.next();
-            }
-        } else {
-            break;
+    /// With a `none` key, ties keep their original order instead of falling back to the path
+    fn keeps_directory_order(&self) -> bool {
+        self.keys.iter().any(|k| k.field == SortField::None)
+    }
+
+    fn compare_field(&self, a: &FileEntry, b: &FileEntry, field: SortField) -> Ordering {
+        match field {
+            SortField::Name => self.collation.compare(a.name(), b.name()),
+            SortField::Size => a.size().cmp(&b.size()),
+            SortField::Modified => a.modified().cmp(&b.modified()),
+            SortField::Created => a.created().cmp(&b.created()),
+            SortField::Type => a.kind().cmp(b.kind()),
+            SortField::Extension => self.collation.compare(
+                a.extension().unwrap_or_default(),
+                b.extension().unwrap_or_default(),
+            ),
+            SortField::Owner => self
+                .collation
+                .compare(a.owner().unwrap_or_default(), b.owner().unwrap_or_default())
+                .then_with(|| a.uid().cmp(&b.uid())),
+            SortField::Group => self
+                .collation
+                .compare(a.group().unwrap_or_default(), b.group().unwrap_or_default())
+                .then_with(|| a.gid().cmp(&b.gid())),
+            SortField::Permissions => (a.mode() & 0o7777).cmp(&(b.mode() & 0o7777)),
+            SortField::Depth => a.depth().cmp(&b.depth()),
+            SortField::FullPath => self
+                .collation
+                .compare(&a.path().to_string_lossy(), &b.path().to_string_lossy()),
+            SortField::Inode => a.inode().cmp(&b.inode()),
+            SortField::Accessed => a.accessed().cmp(&b.accessed()),
+            SortField::NameLength => a.name().chars().count().cmp(&b.name().chars().count()),
+            SortField::None => Ordering::Equal,
         }
     }
-    number
+}
+
+pub fn sort_entries(entries: &mut [FileEntry], field: SortField, order: SortOrder) {
+    Sorter::new(vec![SortKey::new(field, order)]).sort(entries);
+}
+
+/// Sort by a chain of keys with the default collation
+pub fn sort_entries_by(entries: &mut [FileEntry], keys: &[SortKey], group_dirs: DirGrouping) {
+    Sorter::new(keys.to_vec())
+        .group_dirs(group_dirs)
+        .sort(entries);
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    #[test]
-    fn test_natural_sort() {
-        let mut files = vec!["file10.txt", "file2.txt", "file1.txt", "file20.txt"];
-        files.sort_by(|a, b| natural_sort(a, b));
-        assert_eq!(
-            files,
-            vec!["file1.txt", "file2.txt", "file10.txt", "file20.txt",]
-        );
-    }
-
     #[test]
     fn test_sort_field_from_str() {
         assert_eq!(SortField::from_str("name"), Some(SortField::Name));
         assert_eq!(SortField::from_str("size"), Some(SortField::Size));
         assert_eq!(SortField::from_str("modified"), Some(SortField::Modified));
+        assert_eq!(SortField::from_str("ext"), Some(SortField::Extension));
+        assert_eq!(SortField::from_str("owner"), Some(SortField::Owner));
+        assert_eq!(SortField::from_str("group"), Some(SortField::Group));
+        assert_eq!(SortField::from_str("perms"), Some(SortField::Permissions));
+        assert_eq!(SortField::from_str("depth"), Some(SortField::Depth));
+        assert_eq!(SortField::from_str("path"), Some(SortField::FullPath));
+        assert_eq!(SortField::from_str("inode"), Some(SortField::Inode));
+        assert_eq!(SortField::from_str("atime"), Some(SortField::Accessed));
+        assert_eq!(
+            SortField::from_str("name-length"),
+            Some(SortField::NameLength)
+        );
+        assert_eq!(SortField::from_str("none"), Some(SortField::None));
         assert_eq!(SortField::from_str("invalid"), None);
     }
 
+    #[test]
+    fn test_sort_key_parse_list() {
+        assert_eq!(
+            SortKey::parse_list("type,size:desc,name", SortOrder::Ascending),
+            Ok(vec![
+                SortKey::new(SortField::Type, SortOrder::Ascending),
+                SortKey::new(SortField::Size, SortOrder::Descending),
+                SortKey::new(SortField::Name, SortOrder::Ascending),
+            ])
+        );
+        assert_eq!(
+            SortKey::parse_list("name", SortOrder::Descending),
+            Ok(vec![SortKey::new(SortField::Name, SortOrder::Descending)])
+        );
+        assert_eq!(
+            SortKey::parse_list("size:up", SortOrder::Ascending),
+            Err("size:up".to_string())
+        );
+        assert_eq!(
+            SortKey::parse_list("name,color", SortOrder::Ascending),
+            Err("color".to_string())
+        );
+    }
+
+    #[test]
+    fn test_multi_key_sort() {
+        use std::path::PathBuf;
+
+        let entry = |name: &str, is_dir: bool, size: u64| {
+            FileEntry::new(PathBuf::from(name), is_dir, size, None, None)
+        };
+        let mut entries = vec![
+            entry("b.txt", false, 10),
+            entry("a.rs", false, 10),
+            entry("src", true, 0),
+            entry("c.txt", false, 30),
+            entry("docs", true, 0),
+        ];
+
+        let keys = SortKey::parse_list("size:desc,name", SortOrder::Ascending).unwrap();
+        sort_entries_by(&mut entries, &keys, DirGrouping::First);
+        let names: Vec<_> = entries.iter().map(|e| e.name()).collect();
+        assert_eq!(names, vec!["docs", "src", "c.txt", "a.rs", "b.txt"]);
+
+        sort_entries_by(&mut entries, &keys, DirGrouping::Last);
+        let names: Vec<_> = entries.iter().map(|e| e.name()).collect();
+        assert_eq!(names, vec!["c.txt", "a.rs", "b.txt", "docs", "src"]);
+
+        // Full ties fall back to path order, independent of input order
+        let mut tied = vec![entry("z", false, 1), entry("y", false, 1)];
+        sort_entries_by(
+            &mut tied,
+            &[SortKey::new(SortField::Size, SortOrder::Ascending)],
+            DirGrouping::None,
+        );
+        assert_eq!(tied[0].name(), "y");
+    }
+
+    #[test]
+    fn test_type_sort_compares_kind_only() {
+        use std::path::PathBuf;
+
+        let entry = |name: &str, mode: u32| {
+            FileEntry::new(
+                PathBuf::from(name),
+                mode & 0o170000 == 0o040000,
+                0,
+                None,
+                None,
+            )
+            .with_mode(mode)
+        };
+        let mut entries = vec![
+            entry("b.txt", 0o100644),
+            entry("link", 0o120777),
+            entry("a.zip", 0o100644),
+            entry("src", 0o040755),
+        ];
+        let keys = SortKey::parse_list("type:desc,name", SortOrder::Ascending).unwrap();
+        sort_entries_by(&mut entries, &keys, DirGrouping::None);
+        let names: Vec<_> = entries.iter().map(|e| e.name()).collect();
+        // Files tie on type and fall through to the name, not the extension
+        assert_eq!(names, vec!["link", "a.zip", "b.txt", "src"]);
+
+        sort_entries_by(&mut entries, &keys, DirGrouping::First);
+        let names: Vec<_> = entries.iter().map(|e| e.name()).collect();
+        assert_eq!(names, vec!["src", "link", "a.zip", "b.txt"]);
+    }
+
+    #[test]
+    fn test_depth_sort_uses_walk_depth() {
+        use std::path::PathBuf;
+
+        // The depth is the nesting level below the listed directory as set by the
+        // walker, not the number of components in the path
+        let entry = |path: &str, depth: usize| {
+            FileEntry::new(PathBuf::from(path), false, 0, None, None).with_depth(depth)
+        };
+        let mut entries = vec![
+            entry("inner.txt", 2),
+            entry("/srv/data/project/top.txt", 0),
+            entry("docs/guide.md", 1),
+        ];
+        Sorter::new(vec![SortKey::new(SortField::Depth, SortOrder::Ascending)]).sort(&mut entries);
+        let names: Vec<_> = entries.iter().map(|e| e.name()).collect();
+        assert_eq!(names, vec!["top.txt", "guide.md", "inner.txt"]);
+    }
+
+    #[test]
+    fn test_additional_sort_fields() {
+        use std::path::PathBuf;
+
+        let entry = |path: &str, mode: u32| {
+            let depth = path.matches('/').count();
+            FileEntry::new(PathBuf::from(path), false, 0, None, None)
+                .with_mode(mode)
+                .with_depth(depth)
+        };
+        let original = vec![
+            entry("src/lib.rs", 0o100644),
+            entry("a/b/c/notes.txt", 0o100600),
+            entry("README.md", 0o100755),
+            entry("Makefile", 0o100644),
+        ];
+        let names = |field: SortField| {
+            let mut entries = original.clone();
+            Sorter::new(vec![SortKey::new(field, SortOrder::Ascending)]).sort(&mut entries);
+            entries
+                .iter()
+                .map(|e| e.name().to_string())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            names(SortField::Extension),
+            vec!["Makefile", "README.md", "lib.rs", "notes.txt"]
+        );
+        assert_eq!(
+            names(SortField::Depth),
+            vec!["Makefile", "README.md", "lib.rs", "notes.txt"]
+        );
+        assert_eq!(
+            names(SortField::FullPath),
+            vec!["Makefile", "README.md", "notes.txt", "lib.rs"]
+        );
+        assert_eq!(
+            names(SortField::Permissions),
+            vec!["notes.txt", "Makefile", "lib.rs", "README.md"]
+        );
+        assert_eq!(
+            names(SortField::NameLength),
+            vec!["lib.rs", "Makefile", "README.md", "notes.txt"]
+        );
+        assert_eq!(
+            names(SortField::None),
+            vec!["lib.rs", "notes.txt", "README.md", "Makefile"]
+        );
+    }
+
+    #[test]
+    fn test_hierarchical_sort() {
+        use std::path::PathBuf;
+
+        let entry = |path: &str, is_dir: bool, size: u64| {
+            FileEntry::new(PathBuf::from(path), is_dir, size, None, None)
+        };
+        let mut entries = vec![
+            entry("root/small.txt", false, 1),
+            entry("root/big", true, 4096),
+            entry("root/mid.txt", false, 50),
+            entry("root/big/huge.bin", false, 9000),
+            entry("root/big/tiny.bin", false, 2),
+        ];
+
+        let sorter = Sorter::new(vec![SortKey::new(SortField::Size, SortOrder::Descending)]);
+        sorter.sort_hierarchical(&mut entries);
+        let paths: Vec<_> = entries.iter().map(|e| e.path().to_str().unwrap()).collect();
+        assert_eq!(
+            paths,
+            vec![
+                "root/big",
+                "root/big/huge.bin",
+                "root/big/tiny.bin",
+                "root/mid.txt",
+                "root/small.txt",
+            ]
+        );
+
+        // Without their parent directory, children stay grouped per directory
+        let mut files: Vec<_> = entries.into_iter().filter(|e| !e.is_dir()).collect();
+        sorter.sort_hierarchical(&mut files);
+        let paths: Vec<_> = files.iter().map(|e| e.path().to_str().unwrap()).collect();
+        assert_eq!(
+            paths,
+            vec![
+                "root/mid.txt",
+                "root/small.txt",
+                "root/big/huge.bin",
+                "root/big/tiny.bin",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_sorter_collation() {
+        use std::path::PathBuf;
+
+        let mut entries: Vec<_> = ["b.txt", "Cat.txt", "a.txt"]
+            .iter()
+            .map(|name| FileEntry::new(PathBuf::from(name), false, 0, None, None))
+            .collect();
+
+        let sorter = Sorter::new(vec![SortKey::new(SortField::Name, SortOrder::Ascending)]);
+        sorter.sort(&mut entries);
+        assert_eq!(entries[0].name(), "Cat.txt");
+
+        sorter.collation(Collation::IgnoreCase).sort(&mut entries);
+        let names: Vec<_> = entries.iter().map(|e| e.name()).collect();
+        assert_eq!(names, vec!["a.txt", "b.txt", "Cat.txt"]);
+    }
+
     #[test]
     fn test_sort_order_from_str() {
         assert_eq!(SortOrder::from_str("asc"), Some(SortOrder::Ascending));
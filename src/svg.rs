@@ -0,0 +1,562 @@
+use crate::colors::ColorTheme;
+use crate::file_entry::FileEntry;
+use crate::html::escape;
+use crate::output::Renderer;
+use crate::size_utils::SizeFormat;
+use crate::tree::{DirTotals, EntryTree};
+use chrono::Local;
+use std::f64::consts::PI;
+use std::io::{self, Write};
+use std::path::Path;
+use termcolor::{Color, WriteColor};
+
+const TREEMAP_WIDTH: f64 = 1200.0;
+const TREEMAP_HEIGHT: f64 = 800.0;
+const SUNBURST_SIZE: f64 = 800.0;
+/// Height of the age legend below the chart
+const LEGEND_HEIGHT: f64 = 28.0;
+/// Height of the name strip at the top of a treemap directory
+const LABEL_HEIGHT: f64 = 16.0;
+/// Approximate width of a label character, to decide whether a name fits
+const CHAR_WIDTH: f64 = 7.0;
+
+const DIR_FILL: &str = "#e4ebf5";
+const OTHER_FILL: &str = "#b0b0b0";
+/// Age buckets for `--chart-color age`: upper bound in days, fill and legend label
+const AGE_BUCKETS: [(i64, &str, &str); 5] = [
+    (1, "#d73027", "< 1 day"),
+    (7, "#fc8d59", "< 1 week"),
+    (30, "#fee08b", "< 1 month"),
+    (365, "#91bfdb", "< 1 year"),
+    (i64::MAX, "#4575b4", "older"),
+];
+
+/// Kind of disk usage chart
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Chart {
+    /// Nested rectangles with areas proportional to size
+    Treemap,
+    /// Rings of arcs, one ring per directory level
+    Sunburst,
+}
+
+/// What the fill color of a chart shape shows
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum ChartColor {
+    /// The color theme's palette color of the file type, whether or not the
+    /// terminal output is colored
+    #[default]
+    Kind,
+    /// How long ago the file was modified
+    Age,
+}
+
+impl ChartColor {
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s.to_lowercase().as_str() {
+            "kind" | "type" => Some(ChartColor::Kind),
+            "age" | "mtime" => Some(ChartColor::Age),
+            _ => None,
+        }
+    }
+}
+
+/// Standalone SVG chart of a recursive listing, sized by the cumulative size of
+/// each directory, with the path and size of every shape as a hover tooltip
+pub struct ChartRenderer<'a> {
+    root: &'a Path,
+    chart: Chart,
+    color: ChartColor,
+    /// Always enabled, so `--no-color` does not turn the chart grey
+    palette: ColorTheme,
+    size_format: SizeFormat,
+}
+
+impl<'a> ChartRenderer<'a> {
+    pub fn new(root: &'a Path, chart: Chart) -> Self {
+        Self {
+            root,
+            chart,
+            color: ChartColor::default(),
+            palette: ColorTheme::new(true),
+            size_format: SizeFormat::default(),
+        }
+    }
+
+    pub fn color_by(mut self, color: ChartColor) -> Self {
+        self.color = color;
+        self
+    }
+
+    /// Format used for sizes in tooltips and labels
+    pub fn size_format(mut self, size_format: SizeFormat) -> Self {
+        self.size_format = size_format;
+        self
+    }
+
+    fn fill(&self, entry: &FileEntry) -> &'static str {
+        match self.color {
+            ChartColor::Kind => palette_hex(self.palette.get_color_for_path(entry.path()))
+                .unwrap_or(if entry.is_dir() { DIR_FILL } else { OTHER_FILL }),
+            ChartColor::Age if entry.is_dir() => DIR_FILL,
+            ChartColor::Age => {
+                let days = (Local::now() - entry.modified()).num_days();
+                AGE_BUCKETS
+                    .iter()
+                    .find(|(limit, _, _)| days < *limit)
+                    .map_or(OTHER_FILL, |(_, fill, _)| fill)
+            }
+        }
+    }
+
+    fn tooltip(&self, entry: &FileEntry, totals: DirTotals) -> String {
+        let path = entry.path().display().to_string();
+        if entry.is_dir() {
+            format!(
+                "{}/ — {} in {} files",
+                path,
+                self.size_format.format(totals.size),
+                totals.files
+            )
+        } else {
+            format!("{} — {}", path, self.size_format.format(totals.size))
+        }
+    }
+
+    fn write_treemap(
+        &self,
+        chart: &ChartData,
+        nodes: &[usize],
+        area: Rect,
+        out: &mut dyn Write,
+    ) -> io::Result<()> {
+        let nodes = chart.by_size(nodes);
+        let sizes: Vec<f64> = nodes.iter().map(|&n| chart.totals[n].size as f64).collect();
+
+        for (&node, rect) in nodes.iter().zip(squarify(&sizes, area)) {
+            if rect.w < 1.0 || rect.h < 1.0 {
+                continue;
+            }
+            let entry = &chart.entries[node];
+            writeln!(
+                out,
+                "<g><title>{}</title><rect x=\"{:.1}\" y=\"{:.1}\" width=\"{:.1}\" height=\"{:.1}\" fill=\"{}\"/>",
+                escape(&self.tooltip(entry, chart.totals[node])),
+                rect.x,
+                rect.y,
+                rect.w,
+                rect.h,
+                self.fill(entry)
+            )?;
+
+            let fits_label = rect.h > LABEL_HEIGHT * 2.0
+                && rect.w > (entry.name().chars().count() as f64 + 1.0) * CHAR_WIDTH;
+            if fits_label {
+                writeln!(
+                    out,
+                    "<text x=\"{:.1}\" y=\"{:.1}\">{}</text>",
+                    rect.x + 3.0,
+                    rect.y + LABEL_HEIGHT - 4.0,
+                    escape(entry.name())
+                )?;
+            }
+            writeln!(out, "</g>")?;
+
+            let children = chart.tree.children(node);
+            if entry.is_dir() && !children.is_empty() && rect.w > 4.0 && rect.h > 4.0 {
+                // Leave a margin, and room for the name when it was drawn
+                let top = if fits_label { LABEL_HEIGHT } else { 2.0 };
+                let inner = Rect {
+                    x: rect.x + 2.0,
+                    y: rect.y + top,
+                    w: rect.w - 4.0,
+                    h: rect.h - top - 2.0,
+                };
+                if inner.h > 1.0 {
+                    self.write_treemap(chart, children, inner, out)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn write_sunburst(&self, chart: &ChartData, out: &mut dyn Write) -> io::Result<()> {
+        let center = SUNBURST_SIZE / 2.0;
+        let levels = chart
+            .entries
+            .iter()
+            .zip(&chart.totals)
+            .filter(|(_, t)| t.size > 0)
+            .map(|(e, _)| e.depth() + 1)
+            .max()
+            .unwrap_or(0);
+        let ring = (center - 10.0) / (levels + 1) as f64;
+
+        let root_totals = chart.root_totals();
+        writeln!(
+            out,
+            "<g><title>{}</title><circle cx=\"{:.1}\" cy=\"{:.1}\" r=\"{:.1}\" fill=\"{}\"/></g>",
+            escape(&format!(
+                "{}/ — {} in {} files",
+                self.root.display(),
+                self.size_format.format(root_totals.size),
+                root_totals.files
+            )),
+            center,
+            center,
+            ring,
+            DIR_FILL
+        )?;
+
+        let arcs = Arcs {
+            center,
+            ring,
+            start: 0.0,
+            sweep: 2.0 * PI,
+            level: 1,
+        };
+        self.write_arcs(chart, chart.tree.roots(), arcs, out)
+    }
+
+    fn write_arcs(
+        &self,
+        chart: &ChartData,
+        nodes: &[usize],
+        arcs: Arcs,
+        out: &mut dyn Write,
+    ) -> io::Result<()> {
+        let nodes = chart.by_size(nodes);
+        let total: u64 = nodes.iter().map(|&n| chart.totals[n].size).sum();
+        if total == 0 {
+            return Ok(());
+        }
+
+        let mut start = arcs.start;
+        for node in nodes {
+            let sweep = arcs.sweep * chart.totals[node].size as f64 / total as f64;
+            // Arcs too thin to see or hover are left out, with their children
+            if sweep >= 0.002 {
+                let entry = &chart.entries[node];
+                let inner = arcs.ring * arcs.level as f64;
+                writeln!(
+                    out,
+                    "<g><title>{}</title><path d=\"{}\" fill=\"{}\"/></g>",
+                    escape(&self.tooltip(entry, chart.totals[node])),
+                    arc_path(arcs.center, inner, inner + arcs.ring, start, sweep),
+                    self.fill(entry)
+                )?;
+                let children = chart.tree.children(node);
+                if !children.is_empty() {
+                    let arcs = Arcs {
+                        start,
+                        sweep,
+                        level: arcs.level + 1,
+                        ..arcs
+                    };
+                    self.write_arcs(chart, children, arcs, out)?;
+                }
+            }
+            start += sweep;
+        }
+        Ok(())
+    }
+
+    fn write_legend(&self, y: f64, out: &mut dyn Write) -> io::Result<()> {
+        let mut x = 4.0;
+        for (_, fill, label) in AGE_BUCKETS {
+            writeln!(
+                out,
+                "<rect x=\"{:.1}\" y=\"{:.1}\" width=\"12\" height=\"12\" fill=\"{}\"/>\
+                 <text x=\"{:.1}\" y=\"{:.1}\">{}</text>",
+                x,
+                y + 8.0,
+                fill,
+                x + 16.0,
+                y + 18.0,
+                escape(label)
+            )?;
+            x += 16.0 + (label.len() as f64 + 3.0) * CHAR_WIDTH;
+        }
+        Ok(())
+    }
+}
+
+impl Renderer for ChartRenderer<'_> {
+    fn render(&self, entries: &[FileEntry], out: &mut dyn WriteColor) -> io::Result<()> {
+        let tree = EntryTree::new(entries);
+        let totals = tree.totals(entries);
+        let chart = ChartData {
+            entries,
+            tree,
+            totals,
+        };
+
+        let (width, chart_height) = match self.chart {
+            Chart::Treemap => (TREEMAP_WIDTH, TREEMAP_HEIGHT),
+            Chart::Sunburst => (SUNBURST_SIZE, SUNBURST_SIZE),
+        };
+        let height = match self.color {
+            ChartColor::Age => chart_height + LEGEND_HEIGHT,
+            ChartColor::Kind => chart_height,
+        };
+
+        writeln!(
+            out,
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" viewBox=\"0 0 {} {}\" \
+             font-family=\"sans-serif\" font-size=\"11\">",
+            width, height, width, height
+        )?;
+        writeln!(
+            out,
+            "<title>{}</title>",
+            escape(&format!("ptlist: {}", self.root.display()))
+        )?;
+        writeln!(
+            out,
+            "<style>rect, path {{ stroke: #fff; stroke-width: 0.5; }} g:hover > rect, g:hover > path {{ opacity: 0.8; }} text {{ pointer-events: none; }}</style>"
+        )?;
+
+        match self.chart {
+            Chart::Treemap => {
+                let area = Rect {
+                    x: 0.0,
+                    y: 0.0,
+                    w: width,
+                    h: chart_height,
+                };
+                self.write_treemap(&chart, chart.tree.roots(), area, out)?;
+            }
+            Chart::Sunburst => self.write_sunburst(&chart, out)?,
+        }
+        if self.color == ChartColor::Age {
+            self.write_legend(chart_height, out)?;
+        }
+        writeln!(out, "</svg>")
+    }
+}
+
+/// Entries with their tree index and cumulative sizes
+struct ChartData<'a> {
+    entries: &'a [FileEntry],
+    tree: EntryTree,
+    totals: Vec<DirTotals>,
+}
+
+impl ChartData<'_> {
+    /// Nodes that take up space, largest first
+    fn by_size(&self, nodes: &[usize]) -> Vec<usize> {
+        let mut nodes: Vec<usize> = nodes
+            .iter()
+            .copied()
+            .filter(|&n| self.totals[n].size > 0)
+            .collect();
+        nodes.sort_by(|&a, &b| self.totals[b].size.cmp(&self.totals[a].size));
+        nodes
+    }
+
+    fn root_totals(&self) -> DirTotals {
+        self.tree
+            .roots()
+            .iter()
+            .fold(DirTotals::default(), |sum, &node| DirTotals {
+                size: sum.size + self.totals[node].size,
+                files: sum.files + self.totals[node].files,
+            })
+    }
+}
+
+/// Ring segment of a sunburst: angles in radians, clockwise from the top
+#[derive(Debug, Clone, Copy)]
+struct Arcs {
+    center: f64,
+    ring: f64,
+    start: f64,
+    sweep: f64,
+    level: usize,
+}
+
+/// SVG path of the ring segment between two radii and two angles
+fn arc_path(center: f64, inner: f64, outer: f64, start: f64, sweep: f64) -> String {
+    // A closed ring would start and end on the same point, which SVG arcs cannot draw
+    let sweep = sweep.min(2.0 * PI - 1e-4);
+    let end = start + sweep;
+    let point = |r: f64, a: f64| (center + r * a.sin(), center - r * a.cos());
+    let large = u8::from(sweep > PI);
+    let (x0, y0) = point(outer, start);
+    let (x1, y1) = point(outer, end);
+    let (x2, y2) = point(inner, end);
+    let (x3, y3) = point(inner, start);
+    format!(
+        "M{:.1},{:.1}A{:.1},{:.1} 0 {} 1 {:.1},{:.1}L{:.1},{:.1}A{:.1},{:.1} 0 {} 0 {:.1},{:.1}Z",
+        x0, y0, outer, outer, large, x1, y1, x2, y2, inner, inner, large, x3, y3
+    )
+}
+
+/// Hex color for a `ColorTheme` palette color
+fn palette_hex(color: Option<Color>) -> Option<&'static str> {
+    match color? {
+        Color::Blue => Some("#5b8fd9"),
+        Color::Green => Some("#5cb85c"),
+        Color::Red => Some("#e06666"),
+        Color::Yellow => Some("#e6b93c"),
+        Color::Magenta => Some("#b86fcf"),
+        Color::Cyan => Some("#4fb8c9"),
+        Color::White => Some("#d6d6d6"),
+        _ => None,
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Rect {
+    x: f64,
+    y: f64,
+    w: f64,
+    h: f64,
+}
+
+/// Split `area` into one rectangle per value, with areas proportional to the values
+/// and aspect ratios kept close to 1 (Bruls, Huizing and van Wijk's squarified
+/// treemap). Values should be positive and sorted largest first.
+fn squarify(values: &[f64], area: Rect) -> Vec<Rect> {
+    let total: f64 = values.iter().sum();
+    if total <= 0.0 {
+        return Vec::new();
+    }
+    let scale = area.w * area.h / total;
+    let areas: Vec<f64> = values.iter().map(|v| v * scale).collect();
+
+    // Worst aspect ratio of a row of areas laid along a side
+    let worst = |row: &[f64], side: f64| {
+        let sum: f64 = row.iter().sum();
+        let max = row.iter().copied().fold(f64::MIN, f64::max);
+        let min = row.iter().copied().fold(f64::MAX, f64::min);
+        let (side2, sum2) = (side * side, sum * sum);
+        (side2 * max / sum2).max(sum2 / (side2 * min))
+    };
+
+    let mut rects = Vec::with_capacity(areas.len());
+    let mut free = area;
+    let mut start = 0;
+    while start < areas.len() {
+        let side = free.w.min(free.h);
+        let mut end = start + 1;
+        while end < areas.len()
+            && worst(&areas[start..=end], side) <= worst(&areas[start..end], side)
+        {
+            end += 1;
+        }
+
+        let row = &areas[start..end];
+        let row_sum: f64 = row.iter().sum();
+        if free.w >= free.h {
+            // A column along the left edge
+            let w = row_sum / free.h;
+            let mut y = free.y;
+            for a in row {
+                let h = a / w;
+                rects.push(Rect { x: free.x, y, w, h });
+                y += h;
+            }
+            free.x += w;
+            free.w -= w;
+        } else {
+            // A row along the top edge
+            let h = row_sum / free.w;
+            let mut x = free.x;
+            for a in row {
+                let w = a / h;
+                rects.push(Rect { x, y: free.y, w, h });
+                x += w;
+            }
+            free.y += h;
+            free.h -= h;
+        }
+        start = end;
+    }
+    rects
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+    use termcolor::NoColor;
+
+    #[test]
+    fn test_squarify() {
+        // The example from the squarified treemap paper
+        let values = [6.0, 6.0, 4.0, 3.0, 2.0, 2.0, 1.0];
+        let area = Rect {
+            x: 0.0,
+            y: 0.0,
+            w: 6.0,
+            h: 4.0,
+        };
+        let rects = squarify(&values, area);
+
+        assert_eq!(rects.len(), values.len());
+        for (rect, value) in rects.iter().zip(values) {
+            assert!((rect.w * rect.h - value).abs() < 1e-9);
+            assert!(rect.x >= 0.0 && rect.x + rect.w <= 6.0 + 1e-9);
+            assert!(rect.y >= 0.0 && rect.y + rect.h <= 4.0 + 1e-9);
+        }
+        // The two largest share the first column
+        assert_eq!(
+            rects[0],
+            Rect {
+                x: 0.0,
+                y: 0.0,
+                w: 3.0,
+                h: 2.0
+            }
+        );
+        assert!(squarify(&[], area).is_empty());
+    }
+
+    #[test]
+    fn test_arc_path() {
+        assert_eq!(
+            arc_path(100.0, 10.0, 20.0, 0.0, PI / 2.0),
+            "M100.0,80.0A20.0,20.0 0 0 1 120.0,100.0L110.0,100.0A10.0,10.0 0 0 0 100.0,90.0Z"
+        );
+    }
+
+    fn render(chart: Chart, color: ChartColor) -> String {
+        let entries = vec![
+            FileEntry::new(PathBuf::from("root/src"), true, 4096, None, None),
+            FileEntry::new(PathBuf::from("root/src/main.rs"), false, 3000, None, None)
+                .with_depth(1),
+            FileEntry::new(PathBuf::from("root/<a>.txt"), false, 1000, None, None),
+            FileEntry::new(PathBuf::from("root/empty"), false, 0, None, None),
+        ];
+        let root = PathBuf::from("root");
+        let mut out = NoColor::new(Vec::new());
+        ChartRenderer::new(&root, chart)
+            .color_by(color)
+            .render(&entries, &mut out)
+            .unwrap();
+        String::from_utf8(out.into_inner()).unwrap()
+    }
+
+    #[test]
+    fn test_treemap() {
+        let svg = render(Chart::Treemap, ChartColor::Kind);
+        assert!(svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\""));
+        assert!(svg.trim_end().ends_with("</svg>"));
+        assert!(svg.contains("<title>root/src/ — 2.9 KiB in 1 files</title>"));
+        assert!(svg.contains("<title>root/&lt;a&gt;.txt — 1000 B</title>"));
+        assert!(!svg.contains("empty"));
+        // main.rs takes three quarters of the canvas, inside src
+        assert!(svg.contains("fill=\"#e06666\""));
+    }
+
+    #[test]
+    fn test_sunburst_by_age() {
+        let svg = render(Chart::Sunburst, ChartColor::Age);
+        assert_eq!(svg.matches("<path").count(), 3);
+        assert!(svg.contains("<circle"));
+        assert!(svg.contains("&lt; 1 day"));
+        assert!(svg.contains("fill=\"#d73027\""));
+    }
+}
@@ -0,0 +1,476 @@
+use crate::colors::ColorTheme;
+use crate::columns::Column;
+use crate::file_entry::FileEntry;
+use crate::size_utils::SizeFormat;
+use chrono::format::{Item, StrftimeItems};
+use std::io;
+use std::iter::Peekable;
+use std::str::Chars;
+use termcolor::{Color, ColorSpec, WriteColor};
+use unicode_width::UnicodeWidthStr;
+
+/// A user-defined line format for `--format template`.
+///
+/// - `{field}` or `{field:spec}` inserts a field; fields are the `--columns` names
+/// - `spec` is `[[fill]align][width]` followed by `h` for human-readable sizes,
+///   or a strftime format for timestamps, e.g. `{size:>10h}` or `{mtime:%F %R}`
+/// - `{?field}...{/}` keeps its contents only when the field is non-empty,
+///   `{!field}...{/}` only when it is empty; `dir`, `file`, `executable` and
+///   `hidden` can be tested as well
+/// - `{@red}`, `{@bold}`, `{@underline}` etc. set styles, `{@auto}` uses the
+///   theme's color for the entry and `{@reset}` clears them
+/// - `{{`, `}}`, `\n`, `\t` and `\\` are literal braces, newline, tab and backslash
+#[derive(Debug, Clone, PartialEq)]
+pub struct Template {
+    nodes: Vec<Node>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Node {
+    Text(String),
+    Field(Column, Spec),
+    Conditional {
+        condition: Condition,
+        negate: bool,
+        body: Vec<Node>,
+    },
+    Style(Style),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Align {
+    Left,
+    Right,
+    Center,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+struct Spec {
+    fill: Option<char>,
+    align: Option<Align>,
+    width: usize,
+    human: bool,
+    time_format: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Condition {
+    NonEmpty(Column),
+    Dir,
+    File,
+    Executable,
+    Hidden,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Style {
+    Color(Color),
+    Bold,
+    Dimmed,
+    Italic,
+    Underline,
+    Auto,
+    Reset,
+}
+
+impl Template {
+    pub fn parse(template: &str) -> Result<Self, String> {
+        let mut chars = template.chars().peekable();
+        let nodes = parse_nodes(&mut chars, false)?;
+        Ok(Self { nodes })
+    }
+
+    /// Render the template for one entry, without a trailing newline
+    pub fn render<W: WriteColor + ?Sized>(
+        &self,
+        entry: &FileEntry,
+        size_format: SizeFormat,
+        color_theme: &ColorTheme,
+        out: &mut W,
+    ) -> io::Result<()> {
+        let mut style = ColorSpec::new();
+        render_nodes(
+            &self.nodes,
+            entry,
+            size_format,
+            color_theme,
+            &mut style,
+            out,
+        )?;
+        if !style.is_none() {
+            out.reset()?;
+        }
+        Ok(())
+    }
+}
+
+fn parse_nodes(chars: &mut Peekable<Chars>, nested: bool) -> Result<Vec<Node>, String> {
+    let mut nodes = Vec::new();
+    let mut text = String::new();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '{' if chars.peek() == Some(&'{') => {
+                chars.next();
+                text.push('{');
+            }
+            '}' if chars.peek() == Some(&'}') => {
+                chars.next();
+                text.push('}');
+            }
+            '}' => return Err("unmatched '}', write '}}' for a literal brace".to_string()),
+            '\\' => match chars.next() {
+                Some('n') => text.push('\n'),
+                Some('t') => text.push('\t'),
+                Some('0') => text.push('\0'),
+                Some('\\') => text.push('\\'),
+                Some(other) => {
+                    text.push('\\');
+                    text.push(other);
+                }
+                None => text.push('\\'),
+            },
+            '{' => {
+                let mut tag = String::new();
+                loop {
+                    match chars.next() {
+                        Some('}') => break,
+                        Some(c) => tag.push(c),
+                        None => return Err(format!("unterminated '{{{}'", tag)),
+                    }
+                }
+
+                if !text.is_empty() {
+                    nodes.push(Node::Text(std::mem::take(&mut text)));
+                }
+
+                if tag == "/" {
+                    if nested {
+                        return Ok(nodes);
+                    }
+                    return Err("'{/}' without a matching '{?...}' or '{!...}'".to_string());
+                } else if let Some(name) = tag.strip_prefix('?') {
+                    nodes.push(Node::Conditional {
+                        condition: parse_condition(name)?,
+                        negate: false,
+                        body: parse_nodes(chars, true)?,
+                    });
+                } else if let Some(name) = tag.strip_prefix('!') {
+                    nodes.push(Node::Conditional {
+                        condition: parse_condition(name)?,
+                        negate: true,
+                        body: parse_nodes(chars, true)?,
+                    });
+                } else if let Some(name) = tag.strip_prefix('@') {
+                    nodes.push(Node::Style(parse_style(name)?));
+                } else {
+                    let (name, spec) = tag.split_once(':').unwrap_or((&tag, ""));
+                    let column = Column::from_str(name.trim())
+                        .ok_or_else(|| format!("unknown field '{}'", name))?;
+                    nodes.push(Node::Field(column, parse_spec(column, spec)?));
+                }
+            }
+            c => text.push(c),
+        }
+    }
+
+    if nested {
+        return Err("conditional segment is missing its closing '{/}'".to_string());
+    }
+    if !text.is_empty() {
+        nodes.push(Node::Text(text));
+    }
+    Ok(nodes)
+}
+
+fn parse_condition(name: &str) -> Result<Condition, String> {
+    match name.trim() {
+        "dir" | "directory" => Ok(Condition::Dir),
+        "file" => Ok(Condition::File),
+        "executable" | "exec" => Ok(Condition::Executable),
+        "hidden" => Ok(Condition::Hidden),
+        other => Column::from_str(other)
+            .map(Condition::NonEmpty)
+            .ok_or_else(|| format!("unknown condition '{}'", other)),
+    }
+}
+
+fn parse_style(name: &str) -> Result<Style, String> {
+    let style = match name.trim().to_lowercase().as_str() {
+        "black" => Style::Color(Color::Black),
+        "red" => Style::Color(Color::Red),
+        "green" => Style::Color(Color::Green),
+        "yellow" => Style::Color(Color::Yellow),
+        "blue" => Style::Color(Color::Blue),
+        "magenta" => Style::Color(Color::Magenta),
+        "cyan" => Style::Color(Color::Cyan),
+        "white" => Style::Color(Color::White),
+        "bold" => Style::Bold,
+        "dim" => Style::Dimmed,
+        "italic" => Style::Italic,
+        "underline" => Style::Underline,
+        "auto" => Style::Auto,
+        "reset" | "/" => Style::Reset,
+        other => return Err(format!("unknown style '{}'", other)),
+    };
+    Ok(style)
+}
+
+fn parse_spec(column: Column, spec: &str) -> Result<Spec, String> {
+    let mut result = Spec::default();
+    let align_of = |c: char| match c {
+        '<' => Some(Align::Left),
+        '>' => Some(Align::Right),
+        '^' => Some(Align::Center),
+        _ => None,
+    };
+
+    let mut rest = spec;
+    let mut prefix = rest.chars();
+    match (prefix.next(), prefix.next()) {
+        (Some(fill), Some(a)) if align_of(a).is_some() => {
+            result.fill = Some(fill);
+            result.align = align_of(a);
+            rest = &rest[fill.len_utf8() + 1..];
+        }
+        (Some(a), _) if align_of(a).is_some() => {
+            result.align = align_of(a);
+            rest = &rest[1..];
+        }
+        _ => {}
+    }
+
+    let digits = rest
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(rest.len());
+    if digits > 0 {
+        result.width = rest[..digits]
+            .parse()
+            .map_err(|_| format!("invalid width in '{}'", spec))?;
+    }
+    rest = &rest[digits..];
+
+    if rest.is_empty() {
+        return Ok(result);
+    }
+    if matches!(column, Column::Size | Column::DiskUsage) && rest == "h" {
+        result.human = true;
+    } else if is_time(column) {
+        if StrftimeItems::new(rest).any(|item| matches!(item, Item::Error)) {
+            return Err(format!("invalid time format '{}'", rest));
+        }
+        result.time_format = Some(rest.to_string());
+    } else {
+        return Err(format!(
+            "unknown modifier '{}' for field '{}'",
+            rest,
+            column.header()
+        ));
+    }
+    Ok(result)
+}
+
+fn is_time(column: Column) -> bool {
+    matches!(
+        column,
+        Column::Modified | Column::Created | Column::Accessed | Column::Changed
+    )
+}
+
+fn field_value(entry: &FileEntry, column: Column, spec: &Spec, size_format: SizeFormat) -> String {
+    if spec.human {
+        return column
+            .bytes(entry)
+            .map(|bytes| size_format.format(bytes))
+            .unwrap_or_default();
+    }
+    if let Some(format) = &spec.time_format {
+        return column
+            .time(entry)
+            .map(|time| time.format(format).to_string())
+            .unwrap_or_default();
+    }
+    column.raw_value(entry)
+}
+
+fn pad(value: String, spec: &Spec, numeric: bool) -> String {
+    let width = value.width();
+    if width >= spec.width {
+        return value;
+    }
+
+    let padding = spec.width - width;
+    let fill = spec.fill.unwrap_or(' ');
+    let default_align = if numeric { Align::Right } else { Align::Left };
+    let (left, right) = match spec.align.unwrap_or(default_align) {
+        Align::Left => (0, padding),
+        Align::Right => (padding, 0),
+        Align::Center => (padding / 2, padding - padding / 2),
+    };
+
+    let mut padded = String::with_capacity(value.len() + padding);
+    padded.extend(std::iter::repeat_n(fill, left));
+    padded.push_str(&value);
+    padded.extend(std::iter::repeat_n(fill, right));
+    padded
+}
+
+fn condition_holds(entry: &FileEntry, condition: Condition) -> bool {
+    match condition {
+        Condition::NonEmpty(column) => !column.raw_value(entry).is_empty(),
+        Condition::Dir => entry.is_dir(),
+        Condition::File => !entry.is_dir(),
+        Condition::Executable => !entry.is_dir() && entry.mode() & 0o111 != 0,
+        Condition::Hidden => entry.name().starts_with('.'),
+    }
+}
+
+fn render_nodes<W: WriteColor + ?Sized>(
+    nodes: &[Node],
+    entry: &FileEntry,
+    size_format: SizeFormat,
+    color_theme: &ColorTheme,
+    style: &mut ColorSpec,
+    out: &mut W,
+) -> io::Result<()> {
+    for node in nodes {
+        match node {
+            Node::Text(text) => out.write_all(text.as_bytes())?,
+            Node::Field(column, spec) => {
+                let value = field_value(entry, *column, spec, size_format);
+                write!(out, "{}", pad(value, spec, column.is_numeric()))?;
+            }
+            Node::Conditional {
+                condition,
+                negate,
+                body,
+            } => {
+                if condition_holds(entry, *condition) != *negate {
+                    render_nodes(body, entry, size_format, color_theme, style, out)?;
+                }
+            }
+            Node::Style(Style::Reset) => {
+                style.clear();
+                out.reset()?;
+            }
+            Node::Style(new_style) => {
+                match new_style {
+                    Style::Color(color) => {
+                        style.set_fg(Some(*color));
+                    }
+                    Style::Bold => {
+                        style.set_bold(true);
+                    }
+                    Style::Dimmed => {
+                        style.set_dimmed(true);
+                    }
+                    Style::Italic => {
+                        style.set_italic(true);
+                    }
+                    Style::Underline => {
+                        style.set_underline(true);
+                    }
+                    Style::Auto => *style = color_theme.color_spec(entry.path()),
+                    Style::Reset => unreachable!(),
+                }
+                out.set_color(style)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+    use termcolor::{Ansi, NoColor};
+
+    fn render(template: &str, entry: &FileEntry) -> String {
+        let mut out = NoColor::new(Vec::new());
+        Template::parse(template)
+            .unwrap()
+            .render(
+                entry,
+                SizeFormat::default(),
+                &ColorTheme::new(false),
+                &mut out,
+            )
+            .unwrap();
+        String::from_utf8(out.into_inner()).unwrap()
+    }
+
+    fn entry() -> FileEntry {
+        FileEntry::new(PathBuf::from("src/main.rs"), false, 2048, None, None).with_mode(0o100755)
+    }
+
+    #[test]
+    fn test_fields_and_alignment() {
+        let entry = entry();
+        assert_eq!(render("{name}", &entry), "main.rs");
+        assert_eq!(render("[{size:>6}]", &entry), "[  2048]");
+        assert_eq!(render("[{size:<6}]", &entry), "[2048  ]");
+        assert_eq!(render("[{size:6}]", &entry), "[  2048]");
+        assert_eq!(render("[{name:*^11}]", &entry), "[**main.rs**]");
+        assert_eq!(render("{size:>8h}", &entry), " 2.0 KiB");
+        assert_eq!(render("{mode} {permissions}", &entry), "0755 -rwxr-xr-x");
+        assert_eq!(render("{{{ext}}}\\t\\n", &entry), "{rs}\t\n");
+    }
+
+    #[test]
+    fn test_time_format() {
+        let entry = entry();
+        let expected = entry.modified().format("%Y-%m-%d").to_string();
+        assert_eq!(render("{mtime:%F}", &entry), expected);
+        assert_eq!(render("{created:%F}", &entry), "");
+    }
+
+    #[test]
+    fn test_conditionals() {
+        let entry = entry();
+        assert_eq!(render("{name}{?dir}/{/}{?exec}*{/}", &entry), "main.rs*");
+        assert_eq!(render("{name}{?matches} ({matches}){/}", &entry), "main.rs");
+        assert_eq!(
+            render(
+                "{name}{?matches} ({matches}){/}",
+                &entry.clone().with_match_count(3)
+            ),
+            "main.rs (3)"
+        );
+        assert_eq!(
+            render("{!created}no birth time{/}", &entry),
+            "no birth time"
+        );
+        assert_eq!(render("{?file}{?exec}x{/}f{/}", &entry), "xf");
+    }
+
+    #[test]
+    fn test_styles() {
+        let mut out = Ansi::new(Vec::new());
+        Template::parse("{@bold}{@red}{name}{@reset} {size}")
+            .unwrap()
+            .render(
+                &entry(),
+                SizeFormat::default(),
+                &ColorTheme::new(true),
+                &mut out,
+            )
+            .unwrap();
+        let rendered = String::from_utf8(out.into_inner()).unwrap();
+        assert!(rendered.contains("\x1b[1m"));
+        assert!(rendered.contains("\x1b[31m"));
+        assert!(rendered.ends_with("\x1b[0m 2048"));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(Template::parse("{nope}").is_err());
+        assert!(Template::parse("{name").is_err());
+        assert!(Template::parse("{name:h}").is_err());
+        assert!(Template::parse("{mtime:%Q}").is_err());
+        assert!(Template::parse("{?dir}unclosed").is_err());
+        assert!(Template::parse("stray {/}").is_err());
+        assert!(Template::parse("}").is_err());
+        assert!(Template::parse("{@sparkly}").is_err());
+    }
+}
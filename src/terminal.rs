@@ -0,0 +1,39 @@
+use std::env;
+
+/// Width used when neither the terminal nor `COLUMNS` provide one
+const DEFAULT_WIDTH: usize = 80;
+
+/// Get the width of the terminal attached to stdout.
+///
+/// Asks the terminal with `TIOCGWINSZ`, falls back to the `COLUMNS`
+/// environment variable, and finally to 80 columns.
+pub fn terminal_width() -> usize {
+    ioctl_width()
+        .or_else(columns_env_width)
+        .unwrap_or(DEFAULT_WIDTH)
+}
+
+#[cfg(unix)]
+fn ioctl_width() -> Option<usize> {
+    let mut size = libc::winsize {
+        ws_row: 0,
+        ws_col: 0,
+        ws_xpixel: 0,
+        ws_ypixel: 0,
+    };
+    // SAFETY: TIOCGWINSZ only writes a `winsize` through the provided pointer
+    let result = unsafe { libc::ioctl(libc::STDOUT_FILENO, libc::TIOCGWINSZ, &mut size) };
+    (result == 0 && size.ws_col > 0).then_some(size.ws_col as usize)
+}
+
+#[cfg(not(unix))]
+fn ioctl_width() -> Option<usize> {
+    None
+}
+
+fn columns_env_width() -> Option<usize> {
+    env::var("COLUMNS")
+        .ok()
+        .and_then(|c| c.trim().parse().ok())
+        .filter(|&c| c > 0)
+}
@@ -0,0 +1,151 @@
+use chrono::{DateTime, Duration, Local, NaiveDate, NaiveDateTime, TimeZone};
+
+/// Parse a human duration such as `90m`, `2d`, `3w` or `1h30m`
+pub fn parse_duration(s: &str) -> Result<Duration, String> {
+    let s = s.trim();
+    if s.is_empty() {
+        return Err("empty duration".to_string());
+    }
+
+    let mut total = Duration::zero();
+    let mut rest = s;
+
+    while !rest.is_empty() {
+        let digits = rest
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(rest.len());
+        if digits == 0 {
+            return Err(format!("invalid duration: {}", s));
+        }
+        let value: i64 = rest[..digits]
+            .parse()
+            .map_err(|_| format!("invalid duration: {}", s))?;
+        rest = &rest[digits..];
+
+        let unit_len = rest
+            .find(|c: char| c.is_ascii_digit())
+            .unwrap_or(rest.len());
+        let unit = &rest[..unit_len];
+        rest = &rest[unit_len..];
+
+        let seconds_per_unit = match unit {
+            "" => return Err(format!("missing unit in duration {} (e.g. 90m, 2d, 3w)", s)),
+            "s" | "sec" | "secs" => 1,
+            "m" | "min" | "mins" => 60,
+            "h" | "hour" | "hours" => 60 * 60,
+            "d" | "day" | "days" => 24 * 60 * 60,
+            "w" | "week" | "weeks" => 7 * 24 * 60 * 60,
+            "mo" | "month" | "months" => 30 * 24 * 60 * 60,
+            "y" | "year" | "years" => 365 * 24 * 60 * 60,
+            _ => return Err(format!("invalid duration unit '{}' in {}", unit, s)),
+        };
+
+        let seconds = value
+            .checked_mul(seconds_per_unit)
+            .ok_or_else(|| format!("duration out of range: {}", s))?;
+        total += Duration::try_seconds(seconds)
+            .ok_or_else(|| format!("duration out of range: {}", s))?;
+    }
+
+    Ok(total)
+}
+
+/// Parse an absolute date/time in local time
+///
+/// Accepts `YYYY-MM-DD`, `YYYY-MM-DD HH:MM[:SS]`, `YYYY-MM-DDTHH:MM[:SS]` and RFC 3339.
+pub fn parse_datetime(s: &str) -> Result<DateTime<Local>, String> {
+    let s = s.trim();
+
+    if let Ok(dt) = DateTime::parse_from_rfc3339(s) {
+        return Ok(dt.with_timezone(&Local));
+    }
+
+    const FORMATS: [&str; 4] = [
+        "%Y-%m-%d %H:%M:%S",
+        "%Y-%m-%dT%H:%M:%S",
+        "%Y-%m-%d %H:%M",
+        "%Y-%m-%dT%H:%M",
+    ];
+    let naive = FORMATS
+        .iter()
+        .find_map(|f| NaiveDateTime::parse_from_str(s, f).ok())
+        .or_else(|| {
+            NaiveDate::parse_from_str(s, "%Y-%m-%d")
+                .ok()
+                .and_then(|d| d.and_hms_opt(0, 0, 0))
+        })
+        .ok_or_else(|| format!("invalid date: {}", s))?;
+
+    Local
+        .from_local_datetime(&naive)
+        .earliest()
+        .ok_or_else(|| format!("invalid local time: {}", s))
+}
+
+/// Resolve a time threshold given either as a duration before `now` or as an absolute date
+pub fn parse_time_spec(s: &str, now: DateTime<Local>) -> Result<DateTime<Local>, String> {
+    if let Ok(duration) = parse_duration(s) {
+        return now
+            .checked_sub_signed(duration)
+            .ok_or_else(|| format!("duration out of range: {}", s));
+    }
+    parse_datetime(s).map_err(|_| {
+        format!(
+            "expected a duration with a unit (e.g. 2d, 90m) or a date (YYYY-MM-DD): {}",
+            s
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("90m"), Ok(Duration::minutes(90)));
+        assert_eq!(parse_duration("2d"), Ok(Duration::days(2)));
+        assert_eq!(parse_duration("3w"), Ok(Duration::weeks(3)));
+        assert_eq!(parse_duration("1h30m"), Ok(Duration::minutes(90)));
+        assert_eq!(parse_duration("45s"), Ok(Duration::seconds(45)));
+        assert!(parse_duration("").is_err());
+        assert!(parse_duration("abc").is_err());
+        assert!(parse_duration("3x").is_err());
+        // A unit is required, so a bare year is not read as a number of days
+        assert!(parse_duration("2025").is_err());
+        assert!(parse_duration("1h30").is_err());
+    }
+
+    #[test]
+    fn test_parse_datetime() {
+        let date = parse_datetime("2025-01-01").unwrap();
+        assert_eq!(
+            date.format("%Y-%m-%d %H:%M:%S").to_string(),
+            "2025-01-01 00:00:00"
+        );
+
+        let datetime = parse_datetime("2025-01-01 12:30").unwrap();
+        assert_eq!(
+            datetime.format("%Y-%m-%d %H:%M:%S").to_string(),
+            "2025-01-01 12:30:00"
+        );
+
+        assert!(parse_datetime("2025-13-01").is_err());
+    }
+
+    #[test]
+    fn test_parse_time_spec() {
+        let now = Local::now();
+        assert_eq!(parse_time_spec("2d", now), Ok(now - Duration::days(2)));
+        assert_eq!(
+            parse_time_spec("2025-01-01", now),
+            parse_datetime("2025-01-01")
+        );
+        assert!(parse_time_spec("yesterday", now).is_err());
+        assert!(
+            parse_time_spec("2025", now)
+                .unwrap_err()
+                .contains("YYYY-MM-DD")
+        );
+    }
+}
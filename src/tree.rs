@@ -0,0 +1,185 @@
+use crate::file_entry::FileEntry;
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Parent/children index over a flat listing, built in linear time.
+///
+/// Nodes are indices into the entry slice the tree was built from. Siblings keep
+/// the order they have in that slice.
+#[derive(Debug)]
+pub struct EntryTree {
+    roots: Vec<usize>,
+    parents: Vec<Option<usize>>,
+    children: Vec<Vec<usize>>,
+}
+
+/// Size and number of files contained in an entry, counting the entry itself
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct DirTotals {
+    pub size: u64,
+    pub files: usize,
+}
+
+impl EntryTree {
+    /// Entries whose parent directory is not part of the listing become roots
+    pub fn new(entries: &[FileEntry]) -> Self {
+        let index: HashMap<&Path, usize> = entries
+            .iter()
+            .enumerate()
+            .map(|(i, e)| (e.path(), i))
+            .collect();
+
+        let mut roots = Vec::new();
+        let mut parents = vec![None; entries.len()];
+        let mut children = vec![Vec::new(); entries.len()];
+
+        for (i, entry) in entries.iter().enumerate() {
+            match entry.path().parent().and_then(|p| index.get(p)) {
+                Some(&parent) if parent != i && entries[parent].is_dir() => {
+                    parents[i] = Some(parent);
+                    children[parent].push(i);
+                }
+                _ => roots.push(i),
+            }
+        }
+
+        Self {
+            roots,
+            parents,
+            children,
+        }
+    }
+
+    pub fn roots(&self) -> &[usize] {
+        &self.roots
+    }
+
+    pub fn parent(&self, node: usize) -> Option<usize> {
+        self.parents[node]
+    }
+
+    pub fn children(&self, node: usize) -> &[usize] {
+        &self.children[node]
+    }
+
+    /// Nodes in depth-first order, each parent before its children
+    pub fn preorder(&self) -> Vec<usize> {
+        let mut order = Vec::with_capacity(self.parents.len());
+        let mut stack: Vec<usize> = self.roots.iter().rev().copied().collect();
+        while let Some(node) = stack.pop() {
+            order.push(node);
+            stack.extend(self.children[node].iter().rev());
+        }
+        order
+    }
+
+    /// Totals for every node: a file counts its own size, a directory the sizes
+    /// and number of all files listed below it
+    pub fn totals(&self, entries: &[FileEntry]) -> Vec<DirTotals> {
+        let mut totals: Vec<DirTotals> = entries
+            .iter()
+            .map(|e| {
+                if e.is_dir() {
+                    DirTotals::default()
+                } else {
+                    DirTotals {
+                        size: e.size(),
+                        files: 1,
+                    }
+                }
+            })
+            .collect();
+        self.sum_up(&mut totals);
+        totals
+    }
+
+    /// Totals as in [`totals`](Self::totals), except that a directory with nothing
+    /// listed below it, as in a listing without --recursive, counts its own size
+    /// instead of being empty; its parents include that size
+    pub fn listed_totals(&self, entries: &[FileEntry]) -> Vec<DirTotals> {
+        let mut totals: Vec<DirTotals> = entries
+            .iter()
+            .enumerate()
+            .map(
+                |(node, e)| match (e.is_dir(), self.children[node].is_empty()) {
+                    (true, false) => DirTotals::default(),
+                    (true, true) => DirTotals {
+                        size: e.size(),
+                        files: 0,
+                    },
+                    (false, _) => DirTotals {
+                        size: e.size(),
+                        files: 1,
+                    },
+                },
+            )
+            .collect();
+        self.sum_up(&mut totals);
+        totals
+    }
+
+    /// Add every node's totals to its ancestors
+    fn sum_up(&self, totals: &mut [DirTotals]) {
+        for node in self.preorder().into_iter().rev() {
+            if let Some(parent) = self.parents[node] {
+                let child = totals[node];
+                totals[parent].size += child.size;
+                totals[parent].files += child.files;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn entry(path: &str, is_dir: bool, size: u64) -> FileEntry {
+        FileEntry::new(PathBuf::from(path), is_dir, size, None, None)
+    }
+
+    #[test]
+    fn test_entry_tree() {
+        let entries = vec![
+            entry("root/b", true, 4096),
+            entry("root/a.txt", false, 10),
+            entry("root/b/c.txt", false, 20),
+            entry("root/b/d", true, 4096),
+            entry("root/b/d/e.txt", false, 30),
+        ];
+        let tree = EntryTree::new(&entries);
+
+        assert_eq!(tree.roots(), &[0, 1]);
+        assert_eq!(tree.children(0), &[2, 3]);
+        assert_eq!(tree.children(3), &[4]);
+        assert_eq!(tree.parent(4), Some(3));
+        assert_eq!(tree.parent(1), None);
+        assert_eq!(tree.preorder(), vec![0, 2, 3, 4, 1]);
+
+        let totals = tree.totals(&entries);
+        assert_eq!(totals[0], DirTotals { size: 50, files: 2 });
+        assert_eq!(totals[3], DirTotals { size: 30, files: 1 });
+        assert_eq!(totals[1], DirTotals { size: 10, files: 1 });
+
+        // Without the contents of root/b/d, it counts its own size
+        let entries = &entries[..4];
+        let tree = EntryTree::new(entries);
+        assert_eq!(tree.totals(entries)[0], DirTotals { size: 20, files: 1 });
+        let totals = tree.listed_totals(entries);
+        assert_eq!(
+            totals[3],
+            DirTotals {
+                size: 4096,
+                files: 0
+            }
+        );
+        assert_eq!(
+            totals[0],
+            DirTotals {
+                size: 4116,
+                files: 1
+            }
+        );
+    }
+}
@@ -0,0 +1,835 @@
+use crate::colors::ColorTheme;
+use crate::file_entry::FileEntry;
+use crate::file_ops::{WalkError, walk};
+use crate::filtering::{FileFilter, filter_entries};
+use crate::size_utils::SizeFormat;
+use crate::sorting::{SortField, SortKey, SortOrder, Sorter};
+use chrono::{DateTime, Local};
+use ratatui::backend::{Backend, CrosstermBackend};
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use ratatui::crossterm::terminal::{
+    EnterAlternateScreen, LeaveAlternateScreen, disable_raw_mode, enable_raw_mode,
+};
+use ratatui::crossterm::{cursor, execute};
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, List, ListItem, ListState, Paragraph};
+use ratatui::{Frame, Terminal};
+use std::io;
+use std::panic;
+use std::path::{Path, PathBuf};
+use std::sync::Once;
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+/// Keys listed in the status line
+const HELP: &str =
+    "↑↓ move  → open  ← up  / filter  space mark  n s m t e sort  q quit  esc cancel";
+
+/// How the browser was left
+#[derive(Debug, PartialEq)]
+pub enum Exit {
+    /// Quit with `q`: the marked paths in marking order, or the directory being
+    /// browsed when nothing is marked
+    Select(Vec<PathBuf>),
+    /// Left with Esc or Ctrl-C
+    Cancel,
+}
+
+/// Full-screen browser for `ptlist tui`, listing one directory at a time through
+/// the walker, filter and sorter used by the other formats
+pub struct Browser<'a> {
+    filter: &'a FileFilter,
+    sorter: Sorter,
+    theme: &'a ColorTheme,
+    size_format: Option<SizeFormat>,
+    /// Directory the browser was opened on; depths are relative to it
+    root: PathBuf,
+    dir: PathBuf,
+    entries: Vec<FileEntry>,
+    /// Indices into `entries` matching the query
+    visible: Vec<usize>,
+    selected: usize,
+    offset: usize,
+    page: usize,
+    query: String,
+    typing: bool,
+    marked: Vec<PathBuf>,
+    status: Option<String>,
+}
+
+impl<'a> Browser<'a> {
+    pub fn new(
+        root: PathBuf,
+        filter: &'a FileFilter,
+        sorter: Sorter,
+        theme: &'a ColorTheme,
+    ) -> Self {
+        Self {
+            filter,
+            sorter,
+            theme,
+            size_format: None,
+            dir: root.clone(),
+            root,
+            entries: Vec::new(),
+            visible: Vec::new(),
+            selected: 0,
+            offset: 0,
+            page: 1,
+            query: String::new(),
+            typing: false,
+            marked: Vec::new(),
+            status: None,
+        }
+    }
+
+    /// Show sizes in the listing in human-readable form
+    pub fn size_format(mut self, size_format: Option<SizeFormat>) -> Self {
+        self.size_format = size_format;
+        self
+    }
+
+    /// Browse on the terminal until the user leaves.
+    ///
+    /// The screen is drawn on stderr so that stdout only carries the selected
+    /// paths, as in `cd "$(ptlist tui)"`.
+    pub fn run(mut self) -> io::Result<Exit> {
+        self.open(self.root.clone())
+            .map_err(|e| io::Error::other(format!("Cannot read {:?}: {}", e.path, e.error)))?;
+
+        let _guard = TerminalGuard::enter()?;
+        let mut terminal = Terminal::new(CrosstermBackend::new(io::stderr()))?;
+        self.event_loop(&mut terminal)
+    }
+
+    fn event_loop<B: Backend<Error = io::Error>>(
+        &mut self,
+        terminal: &mut Terminal<B>,
+    ) -> io::Result<Exit> {
+        loop {
+            terminal.draw(|frame| self.draw(frame))?;
+            if let Event::Key(key) = event::read()?
+                && key.kind == KeyEventKind::Press
+                && let Some(exit) = self.handle_key(key)
+            {
+                return Ok(exit);
+            }
+        }
+    }
+
+    /// Apply one key press, returning how the browser was left if it was
+    pub fn handle_key(&mut self, key: KeyEvent) -> Option<Exit> {
+        self.status = None;
+        if key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c') {
+            return Some(Exit::Cancel);
+        }
+
+        // Movement works while typing a filter; everything else goes to the query
+        match key.code {
+            KeyCode::Up => self.move_by(-1),
+            KeyCode::Down => self.move_by(1),
+            KeyCode::PageUp => self.move_by(-(self.page as isize)),
+            KeyCode::PageDown => self.move_by(self.page as isize),
+            _ if self.typing => match key.code {
+                KeyCode::Char(c) => {
+                    self.query.push(c);
+                    self.refilter();
+                }
+                KeyCode::Backspace => {
+                    self.query.pop();
+                    self.refilter();
+                }
+                KeyCode::Enter => self.typing = false,
+                KeyCode::Esc => {
+                    self.typing = false;
+                    self.query.clear();
+                    self.refilter();
+                }
+                _ => {}
+            },
+            KeyCode::Char('k') => self.move_by(-1),
+            KeyCode::Char('j') => self.move_by(1),
+            KeyCode::Home | KeyCode::Char('g') => self.selected = 0,
+            KeyCode::End | KeyCode::Char('G') => {
+                self.selected = self.visible.len().saturating_sub(1)
+            }
+            KeyCode::Enter | KeyCode::Right | KeyCode::Char('l') => self.enter(),
+            KeyCode::Left | KeyCode::Backspace | KeyCode::Char('h') => self.leave(),
+            KeyCode::Char('/') => self.typing = true,
+            KeyCode::Char(' ') => {
+                self.toggle_mark();
+                self.move_by(1);
+            }
+            KeyCode::Char('n') => self.sort_by(SortField::Name),
+            KeyCode::Char('s') => self.sort_by(SortField::Size),
+            KeyCode::Char('m') => self.sort_by(SortField::Modified),
+            KeyCode::Char('t') => self.sort_by(SortField::Type),
+            KeyCode::Char('e') => self.sort_by(SortField::Extension),
+            KeyCode::Char('q') => {
+                let paths = if self.marked.is_empty() {
+                    vec![self.dir.clone()]
+                } else {
+                    self.marked.clone()
+                };
+                return Some(Exit::Select(paths));
+            }
+            KeyCode::Esc if !self.query.is_empty() => {
+                self.query.clear();
+                self.refilter();
+            }
+            KeyCode::Esc => return Some(Exit::Cancel),
+            _ => {}
+        }
+        None
+    }
+
+    /// Directory being browsed
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Entry under the cursor
+    pub fn current(&self) -> Option<&FileEntry> {
+        self.visible.get(self.selected).map(|&i| &self.entries[i])
+    }
+
+    /// Entries matching the query, in display order
+    pub fn visible(&self) -> impl Iterator<Item = &FileEntry> {
+        self.visible.iter().map(|&i| &self.entries[i])
+    }
+
+    /// Read `dir` and make it the browsed directory
+    fn open(&mut self, dir: PathBuf) -> Result<(), WalkError> {
+        let mut entries = Vec::new();
+        let mut failure = None;
+        let depth = dir
+            .strip_prefix(&self.root)
+            .map(|p| p.components().count())
+            .unwrap_or(0);
+        walk(
+            &dir,
+            0,
+            &|_| {},
+            &mut |entry| {
+                entries.push(entry.with_depth(depth));
+                Ok(())
+            },
+            &mut |path, e| {
+                if path == dir {
+                    failure = Some(WalkError::new(path, e));
+                }
+            },
+        )
+        .expect("collecting entries cannot fail");
+        if let Some(error) = failure {
+            return Err(error);
+        }
+
+        self.entries = filter_entries(entries, self.filter);
+        self.sorter.sort(&mut self.entries);
+        self.dir = dir;
+        self.query.clear();
+        self.typing = false;
+        self.selected = 0;
+        self.offset = 0;
+        self.refilter();
+        Ok(())
+    }
+
+    fn enter(&mut self) {
+        let Some(entry) = self.current().filter(|e| e.is_dir()) else {
+            return;
+        };
+        let dir = entry.path().to_path_buf();
+        if let Err(e) = self.open(dir) {
+            self.status = Some(format!("Cannot read {}: {}", e.path.display(), e.error));
+        }
+    }
+
+    /// Go to the parent directory with the cursor on the one just left
+    fn leave(&mut self) {
+        let Some(parent) = self.dir.parent().map(Path::to_path_buf) else {
+            return;
+        };
+        let previous = self.dir.clone();
+        match self.open(parent) {
+            Ok(()) => self.select_path(&previous),
+            Err(e) => self.status = Some(format!("Cannot read {}: {}", e.path.display(), e.error)),
+        }
+    }
+
+    /// Sort by `field`, reversing the order when it already is the primary key
+    fn sort_by(&mut self, field: SortField) {
+        let order = match self.sorter.keys().first() {
+            Some(key) if key.field == field && key.order == SortOrder::Ascending => {
+                SortOrder::Descending
+            }
+            _ => SortOrder::Ascending,
+        };
+        self.sorter = self
+            .sorter
+            .clone()
+            .with_keys(vec![SortKey::new(field, order)]);
+
+        let current = self.current().map(|e| e.path().to_path_buf());
+        self.sorter.sort(&mut self.entries);
+        self.refilter();
+        if let Some(path) = current {
+            self.select_path(&path);
+        }
+    }
+
+    fn toggle_mark(&mut self) {
+        let Some(path) = self.current().map(|e| e.path().to_path_buf()) else {
+            return;
+        };
+        match self.marked.iter().position(|p| *p == path) {
+            Some(i) => {
+                self.marked.remove(i);
+            }
+            None => self.marked.push(path),
+        }
+    }
+
+    /// Recompute the entries matching the query: a case-insensitive substring of the
+    /// name, or a case-sensitive one when the query has uppercase letters
+    fn refilter(&mut self) {
+        let case_sensitive = self.query.chars().any(char::is_uppercase);
+        let query = if case_sensitive {
+            self.query.clone()
+        } else {
+            self.query.to_lowercase()
+        };
+        self.visible = (0..self.entries.len())
+            .filter(|&i| {
+                let name = self.entries[i].name();
+                if case_sensitive {
+                    name.contains(&query)
+                } else {
+                    name.to_lowercase().contains(&query)
+                }
+            })
+            .collect();
+        self.selected = self.selected.min(self.visible.len().saturating_sub(1));
+    }
+
+    fn select_path(&mut self, path: &Path) {
+        if let Some(i) = self
+            .visible
+            .iter()
+            .position(|&i| self.entries[i].path() == path)
+        {
+            self.selected = i;
+        }
+    }
+
+    fn move_by(&mut self, delta: isize) {
+        let last = self.visible.len().saturating_sub(1);
+        self.selected = self.selected.saturating_add_signed(delta).min(last);
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let [header, body, footer] = Layout::vertical([
+            Constraint::Length(1),
+            Constraint::Min(3),
+            Constraint::Length(1),
+        ])
+        .areas(frame.area());
+        let [list, details] =
+            Layout::horizontal([Constraint::Percentage(60), Constraint::Percentage(40)])
+                .areas(body);
+
+        let sort = self
+            .sorter
+            .keys()
+            .iter()
+            .map(|key| match key.order {
+                SortOrder::Ascending => format!("{:?}", key.field).to_lowercase(),
+                SortOrder::Descending => format!("{:?}:desc", key.field).to_lowercase(),
+            })
+            .collect::<Vec<_>>()
+            .join(",");
+        let title = format!(
+            " {}  [sort {}]  {} of {} entries, {} marked",
+            self.dir.display(),
+            sort,
+            self.visible.len(),
+            self.entries.len(),
+            self.marked.len()
+        );
+        frame.render_widget(
+            Paragraph::new(title).style(Style::new().add_modifier(Modifier::BOLD)),
+            header,
+        );
+
+        self.draw_list(frame, list);
+        self.draw_details(frame, details);
+
+        let status = if self.typing {
+            format!("/{}█", self.query)
+        } else if let Some(status) = &self.status {
+            status.clone()
+        } else if !self.query.is_empty() {
+            format!("filter: {}  (esc clears)  {}", self.query, HELP)
+        } else {
+            HELP.to_string()
+        };
+        frame.render_widget(Paragraph::new(status), footer);
+    }
+
+    fn draw_list(&mut self, frame: &mut Frame, area: Rect) {
+        let block = Block::new().borders(Borders::ALL);
+        let height = block.inner(area).height.max(1) as usize;
+        self.page = height;
+        if self.selected < self.offset {
+            self.offset = self.selected;
+        } else if self.selected >= self.offset + height {
+            self.offset = self.selected + 1 - height;
+        }
+
+        let size_width = 10;
+        let name_width = (block.inner(area).width as usize).saturating_sub(size_width + 3);
+        // Only the rows on screen are styled, since coloring stats each path
+        let items: Vec<ListItem> = self
+            .visible
+            .iter()
+            .skip(self.offset)
+            .take(height)
+            .map(|&i| {
+                let entry = &self.entries[i];
+                let marked = self.marked.iter().any(|p| p == entry.path());
+                let mut name = entry.name().to_string();
+                if entry.is_dir() {
+                    name.push('/');
+                }
+                let name = truncate_to_width(&name, name_width).to_string();
+                let size = match self.size_format {
+                    Some(format) => format.format(entry.size()),
+                    None => entry.size().to_string(),
+                };
+                let padding = name_width.saturating_sub(name.width());
+                ListItem::new(Line::from(vec![
+                    Span::styled(
+                        if marked { "* " } else { "  " },
+                        Style::new().fg(Color::Yellow),
+                    ),
+                    Span::styled(name, self.name_style(entry.path(), marked)),
+                    Span::raw(" ".repeat(padding + 1)),
+                    Span::raw(format!("{:>size_width$}", size)),
+                ]))
+            })
+            .collect();
+
+        let mut state = ListState::default()
+            .with_selected((!self.visible.is_empty()).then(|| self.selected - self.offset));
+        frame.render_stateful_widget(
+            List::new(items)
+                .block(block)
+                .highlight_style(Style::new().add_modifier(Modifier::REVERSED)),
+            area,
+            &mut state,
+        );
+    }
+
+    fn draw_details(&self, frame: &mut Frame, area: Rect) {
+        let lines = match self.current() {
+            Some(entry) => details(entry)
+                .into_iter()
+                .map(|(label, value)| {
+                    Line::from(vec![
+                        Span::styled(
+                            format!("{:<11}", label),
+                            Style::new().add_modifier(Modifier::BOLD),
+                        ),
+                        Span::raw(value),
+                    ])
+                })
+                .collect(),
+            None => vec![Line::from("No entries")],
+        };
+        frame.render_widget(
+            Paragraph::new(lines).block(Block::new().borders(Borders::ALL).title(" Details ")),
+            area,
+        );
+    }
+
+    /// The theme's color for `path`, or yellow for marked entries
+    fn name_style(&self, path: &Path, marked: bool) -> Style {
+        if marked {
+            return Style::new().fg(Color::Yellow);
+        }
+        let spec = self.theme.color_spec(path);
+        let mut style = Style::new();
+        if let Some(color) = spec.fg() {
+            style = style.fg(tui_color(*color));
+        }
+        if spec.bold() {
+            style = style.add_modifier(Modifier::BOLD);
+        }
+        style
+    }
+}
+
+/// Every piece of metadata of `entry`, as label and value
+fn details(entry: &FileEntry) -> Vec<(&'static str, String)> {
+    let time = |t: Option<DateTime<Local>>| match t {
+        Some(t) => t.format("%Y-%m-%d %H:%M:%S").to_string(),
+        None => "-".to_string(),
+    };
+    let id = |name: Option<&str>, id: u32| match name {
+        Some(name) => format!("{} ({})", name, id),
+        None => id.to_string(),
+    };
+
+    let mut details = vec![
+        ("Name", entry.name().to_string()),
+        ("Path", entry.path().display().to_string()),
+        ("Kind", entry.kind().to_string()),
+        (
+            "Size",
+            format!(
+                "{} ({} bytes)",
+                SizeFormat::default().format(entry.size()),
+                entry.size()
+            ),
+        ),
+        (
+            "Disk usage",
+            SizeFormat::default().format(entry.disk_usage()),
+        ),
+        (
+            "Mode",
+            format!("{} ({:04o})", entry.permissions(), entry.mode() & 0o7777),
+        ),
+        ("Owner", id(entry.owner(), entry.uid())),
+        ("Group", id(entry.group(), entry.gid())),
+        ("Links", entry.links().to_string()),
+        ("Inode", entry.inode().to_string()),
+        ("Depth", entry.depth().to_string()),
+        ("Extension", entry.extension().unwrap_or("-").to_string()),
+        ("Modified", time(Some(entry.modified()))),
+        ("Accessed", time(entry.accessed())),
+        ("Changed", time(entry.changed())),
+        ("Created", time(entry.created())),
+    ];
+    if let Some(count) = entry.match_count() {
+        details.push(("Matches", count.to_string()));
+    }
+    details
+}
+
+/// Raw mode and the alternate screen for as long as the browser runs. The
+/// terminal is restored when the guard is dropped, and by a panic hook before
+/// the panic message is printed, so a crash does not leave it unusable.
+struct TerminalGuard;
+
+impl TerminalGuard {
+    fn enter() -> io::Result<Self> {
+        static PANIC_HOOK: Once = Once::new();
+        PANIC_HOOK.call_once(|| {
+            let hook = panic::take_hook();
+            panic::set_hook(Box::new(move |info| {
+                restore_terminal();
+                hook(info);
+            }));
+        });
+
+        enable_raw_mode()?;
+        // From here on, dropping the guard undoes what was set up
+        let guard = TerminalGuard;
+        execute!(io::stderr(), EnterAlternateScreen)?;
+        Ok(guard)
+    }
+}
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        restore_terminal();
+    }
+}
+
+/// Leave the alternate screen and raw mode; harmless when neither is active
+fn restore_terminal() {
+    let _ = execute!(io::stderr(), LeaveAlternateScreen, cursor::Show);
+    let _ = disable_raw_mode();
+}
+
+/// The longest prefix of `name` that fits in `width` terminal columns
+fn truncate_to_width(name: &str, width: usize) -> &str {
+    let mut used = 0;
+    for (i, c) in name.char_indices() {
+        used += c.width().unwrap_or(0);
+        if used > width {
+            return &name[..i];
+        }
+    }
+    name
+}
+
+/// Convert a theme color to the terminal UI's color type
+fn tui_color(color: termcolor::Color) -> Color {
+    match color {
+        termcolor::Color::Black => Color::Black,
+        termcolor::Color::Blue => Color::Blue,
+        termcolor::Color::Green => Color::Green,
+        termcolor::Color::Red => Color::Red,
+        termcolor::Color::Cyan => Color::Cyan,
+        termcolor::Color::Magenta => Color::Magenta,
+        termcolor::Color::Yellow => Color::Yellow,
+        termcolor::Color::White => Color::White,
+        termcolor::Color::Ansi256(n) => Color::Indexed(n),
+        termcolor::Color::Rgb(r, g, b) => Color::Rgb(r, g, b),
+        _ => Color::Reset,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ratatui::backend::TestBackend;
+    use std::fs::{self, File};
+    use std::io::Write;
+    use tempfile::TempDir;
+
+    fn press(browser: &mut Browser, code: KeyCode) -> Option<Exit> {
+        browser.handle_key(KeyEvent::from(code))
+    }
+
+    fn type_text(browser: &mut Browser, text: &str) {
+        for c in text.chars() {
+            press(browser, KeyCode::Char(c));
+        }
+    }
+
+    fn names(browser: &Browser) -> Vec<String> {
+        browser.visible().map(|e| e.name().to_string()).collect()
+    }
+
+    fn setup() -> TempDir {
+        let temp_dir = TempDir::new().unwrap();
+        fs::create_dir(temp_dir.path().join("docs")).unwrap();
+        File::create(temp_dir.path().join("docs/guide.md")).unwrap();
+        File::create(temp_dir.path().join("a.txt"))
+            .unwrap()
+            .write_all(b"hello world")
+            .unwrap();
+        File::create(temp_dir.path().join("b.rs"))
+            .unwrap()
+            .write_all(b"x")
+            .unwrap();
+        File::create(temp_dir.path().join(".hidden")).unwrap();
+        temp_dir
+    }
+
+    fn name_sorter() -> Sorter {
+        Sorter::new(vec![SortKey::new(SortField::Name, SortOrder::Ascending)])
+    }
+
+    #[test]
+    fn test_open_applies_filter_and_sort() {
+        let temp_dir = setup();
+        let filter = FileFilter::new();
+        let theme = ColorTheme::new(false);
+        let mut browser = Browser::new(
+            temp_dir.path().to_path_buf(),
+            &filter,
+            name_sorter(),
+            &theme,
+        );
+        browser.open(temp_dir.path().to_path_buf()).unwrap();
+
+        assert_eq!(names(&browser), ["a.txt", "b.rs", "docs"]);
+        assert_eq!(browser.current().unwrap().name(), "a.txt");
+    }
+
+    #[test]
+    fn test_enter_and_leave_directories() {
+        let temp_dir = setup();
+        let filter = FileFilter::new();
+        let theme = ColorTheme::new(false);
+        let mut browser = Browser::new(
+            temp_dir.path().to_path_buf(),
+            &filter,
+            name_sorter(),
+            &theme,
+        );
+        browser.open(temp_dir.path().to_path_buf()).unwrap();
+
+        // Opening a file does nothing
+        press(&mut browser, KeyCode::Enter);
+        assert_eq!(browser.dir(), temp_dir.path());
+
+        press(&mut browser, KeyCode::End);
+        press(&mut browser, KeyCode::Right);
+        assert_eq!(browser.dir(), temp_dir.path().join("docs"));
+        assert_eq!(names(&browser), ["guide.md"]);
+        assert_eq!(browser.current().unwrap().depth(), 1);
+
+        // Going up puts the cursor back on the directory just left
+        press(&mut browser, KeyCode::Left);
+        assert_eq!(browser.dir(), temp_dir.path());
+        assert_eq!(browser.current().unwrap().name(), "docs");
+    }
+
+    #[test]
+    fn test_filter_as_you_type() {
+        let temp_dir = setup();
+        let filter = FileFilter::new();
+        let theme = ColorTheme::new(false);
+        let mut browser = Browser::new(
+            temp_dir.path().to_path_buf(),
+            &filter,
+            name_sorter(),
+            &theme,
+        );
+        browser.open(temp_dir.path().to_path_buf()).unwrap();
+
+        press(&mut browser, KeyCode::Char('/'));
+        type_text(&mut browser, "S");
+        assert!(names(&browser).is_empty());
+        press(&mut browser, KeyCode::Backspace);
+        type_text(&mut browser, "s");
+        assert_eq!(names(&browser), ["b.rs", "docs"]);
+
+        // Keys are part of the query until the filter is accepted
+        type_text(&mut browser, "q");
+        assert!(names(&browser).is_empty());
+        press(&mut browser, KeyCode::Backspace);
+        press(&mut browser, KeyCode::Enter);
+        assert_eq!(names(&browser), ["b.rs", "docs"]);
+
+        press(&mut browser, KeyCode::Esc);
+        assert_eq!(names(&browser), ["a.txt", "b.rs", "docs"]);
+    }
+
+    #[test]
+    fn test_sort_hotkeys_toggle_direction() {
+        let temp_dir = setup();
+        let filter = FileFilter::new().files_only();
+        let theme = ColorTheme::new(false);
+        let mut browser = Browser::new(
+            temp_dir.path().to_path_buf(),
+            &filter,
+            name_sorter(),
+            &theme,
+        );
+        browser.open(temp_dir.path().to_path_buf()).unwrap();
+
+        press(&mut browser, KeyCode::Char('s'));
+        assert_eq!(names(&browser), ["b.rs", "a.txt"]);
+        // The cursor stays on the same entry
+        assert_eq!(browser.current().unwrap().name(), "a.txt");
+
+        press(&mut browser, KeyCode::Char('s'));
+        assert_eq!(names(&browser), ["a.txt", "b.rs"]);
+        assert_eq!(
+            browser.sorter.keys(),
+            [SortKey::new(SortField::Size, SortOrder::Descending)]
+        );
+    }
+
+    #[test]
+    fn test_marked_paths_on_exit() {
+        let temp_dir = setup();
+        let filter = FileFilter::new();
+        let theme = ColorTheme::new(false);
+        let mut browser = Browser::new(
+            temp_dir.path().to_path_buf(),
+            &filter,
+            name_sorter(),
+            &theme,
+        );
+        browser.open(temp_dir.path().to_path_buf()).unwrap();
+
+        // Without marks, quitting selects the browsed directory
+        assert_eq!(
+            press(&mut browser, KeyCode::Char('q')),
+            Some(Exit::Select(vec![temp_dir.path().to_path_buf()]))
+        );
+
+        press(&mut browser, KeyCode::End);
+        press(&mut browser, KeyCode::Char(' '));
+        press(&mut browser, KeyCode::Home);
+        press(&mut browser, KeyCode::Char(' '));
+        press(&mut browser, KeyCode::Char(' '));
+        press(&mut browser, KeyCode::Up);
+        press(&mut browser, KeyCode::Char(' '));
+        assert_eq!(
+            press(&mut browser, KeyCode::Char('q')),
+            Some(Exit::Select(vec![
+                temp_dir.path().join("docs"),
+                temp_dir.path().join("a.txt"),
+            ]))
+        );
+        assert_eq!(press(&mut browser, KeyCode::Esc), Some(Exit::Cancel));
+    }
+
+    #[test]
+    fn test_draw_shows_details() {
+        let temp_dir = setup();
+        let filter = FileFilter::new();
+        let theme = ColorTheme::new(true);
+        let mut browser = Browser::new(
+            temp_dir.path().to_path_buf(),
+            &filter,
+            name_sorter(),
+            &theme,
+        )
+        .size_format(Some(SizeFormat::default()));
+        browser.open(temp_dir.path().to_path_buf()).unwrap();
+
+        let mut terminal = Terminal::new(TestBackend::new(100, 24)).unwrap();
+        terminal.draw(|frame| browser.draw(frame)).unwrap();
+        let screen: String = terminal
+            .backend()
+            .buffer()
+            .content()
+            .iter()
+            .map(|cell| cell.symbol())
+            .collect();
+
+        assert!(screen.contains("docs/"));
+        assert!(screen.contains("11 bytes"));
+        assert!(screen.contains("[sort name]"));
+    }
+
+    #[test]
+    fn test_truncate_to_width() {
+        assert_eq!(truncate_to_width("report.txt", 6), "report");
+        assert_eq!(truncate_to_width("report.txt", 20), "report.txt");
+        // Wide characters take two columns and are never cut in half
+        assert_eq!(truncate_to_width("日本語.txt", 4), "日本");
+        assert_eq!(truncate_to_width("日本語.txt", 5), "日本");
+    }
+
+    #[test]
+    fn test_draw_keeps_wide_names_out_of_size_column() {
+        let temp_dir = TempDir::new().unwrap();
+        let name = "日本語のとても長いファイル名.txt";
+        File::create(temp_dir.path().join(name))
+            .unwrap()
+            .write_all(b"hello")
+            .unwrap();
+        let filter = FileFilter::new();
+        let theme = ColorTheme::new(true);
+        let mut browser = Browser::new(
+            temp_dir.path().to_path_buf(),
+            &filter,
+            name_sorter(),
+            &theme,
+        );
+        browser.open(temp_dir.path().to_path_buf()).unwrap();
+
+        // The list gets 36 of the 60 columns, too narrow for the name
+        let mut terminal = Terminal::new(TestBackend::new(60, 10)).unwrap();
+        terminal.draw(|frame| browser.draw(frame)).unwrap();
+        let buffer = terminal.backend().buffer();
+        let row: String = (0..buffer.area.width)
+            .map(|x| buffer[(x, 2)].symbol())
+            .collect();
+
+        assert!(row.starts_with("│  日"), "{row}");
+        assert!(row.contains("         5│"), "{row}");
+    }
+}
@@ -6,12 +6,12 @@ use tempfile::TempDir;
 
 #[test]
 fn test_no_args() {
-    Command::cargo_bin("ls_plus").unwrap().assert().success();
+    Command::cargo_bin("ptlist").unwrap().assert().success();
 }
 
 #[test]
 fn test_version() {
-    Command::cargo_bin("ls_plus")
+    Command::cargo_bin("ptlist")
         .unwrap()
         .arg("--version")
         .assert()
@@ -21,7 +21,7 @@ fn test_version() {
 
 #[test]
 fn test_invalid_path() {
-    Command::cargo_bin("ls_plus")
+    Command::cargo_bin("ptlist")
         .unwrap()
         .arg("--path")
         .arg("non_existent_directory")
@@ -32,9 +32,9 @@ fn test_invalid_path() {
 
 #[test]
 fn test_formats() {
-    let formats = ["table", "long", "tree", "json"];
+    let formats = ["table", "grid", "lines", "long", "tree", "json"];
     for format in formats {
-        Command::cargo_bin("ls_plus")
+        Command::cargo_bin("ptlist")
             .unwrap()
             .arg("--format")
             .arg(format)
@@ -45,7 +45,7 @@ fn test_formats() {
 
 #[test]
 fn test_human_readable() {
-    Command::cargo_bin("ls_plus")
+    Command::cargo_bin("ptlist")
         .unwrap()
         .arg("--human-readable")
         .assert()
@@ -63,7 +63,7 @@ fn test_file_filtering() {
     std::fs::create_dir(temp_dir.path().join("test_dir")).unwrap();
 
     // Test extension filter
-    Command::cargo_bin("ls_plus")
+    Command::cargo_bin("ptlist")
         .unwrap()
         .arg("--path")
         .arg(temp_dir.path())
@@ -76,7 +76,7 @@ fn test_file_filtering() {
         .stdout(predicate::str::contains("test.rs").not());
 
     // Test dirs only
-    Command::cargo_bin("ls_plus")
+    Command::cargo_bin("ptlist")
         .unwrap()
         .arg("--path")
         .arg(temp_dir.path())
@@ -88,7 +88,7 @@ fn test_file_filtering() {
         .stdout(predicate::str::contains("test.txt").not());
 
     // Test files only
-    Command::cargo_bin("ls_plus")
+    Command::cargo_bin("ptlist")
         .unwrap()
         .arg("--path")
         .arg(temp_dir.path())
@@ -112,7 +112,7 @@ fn test_sorting() {
     file2.write_all(&[0; 200]).unwrap();
 
     // Test size sorting (descending)
-    let output = Command::cargo_bin("ls_plus")
+    let output = Command::cargo_bin("ptlist")
         .unwrap()
         .arg("--path")
         .arg(temp_dir.path())
@@ -144,7 +144,7 @@ fn test_sorting() {
 
 #[test]
 fn test_no_color() {
-    Command::cargo_bin("ls_plus")
+    Command::cargo_bin("ptlist")
         .unwrap()
         .arg("--no-color")
         .assert()
@@ -153,7 +153,7 @@ fn test_no_color() {
 
 #[test]
 fn test_summary() {
-    Command::cargo_bin("ls_plus")
+    Command::cargo_bin("ptlist")
         .unwrap()
         .arg("--summary")
         .assert()
@@ -163,3 +163,1166 @@ fn test_summary() {
         .stdout(predicate::str::contains("Directories:"))
         .stdout(predicate::str::contains("Total Size:"));
 }
+
+#[test]
+fn test_summary_of_data_formats_goes_to_stderr() {
+    let temp_dir = TempDir::new().unwrap();
+    File::create(temp_dir.path().join("a.txt")).unwrap();
+
+    let output = Command::cargo_bin("ptlist")
+        .unwrap()
+        .arg("--path")
+        .arg(temp_dir.path())
+        .args(["--format", "csv", "--summary"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert!(
+        String::from_utf8(output.stdout)
+            .unwrap()
+            .starts_with("name,")
+    );
+    assert!(
+        String::from_utf8(output.stderr)
+            .unwrap()
+            .contains("Summary:")
+    );
+
+    let output = Command::cargo_bin("ptlist")
+        .unwrap()
+        .arg("--path")
+        .arg(temp_dir.path())
+        .args(["--format", "json", "--summary"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let document: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(document["entries"][0]["name"], "a.txt");
+    assert!(
+        String::from_utf8(output.stderr)
+            .unwrap()
+            .contains("Files: 1")
+    );
+}
+
+#[test]
+fn test_time_filtering() {
+    let temp_dir = TempDir::new().unwrap();
+    File::create(temp_dir.path().join("fresh.txt")).unwrap();
+
+    // A file created just now is newer than one hour ago
+    Command::cargo_bin("ptlist")
+        .unwrap()
+        .arg("--path")
+        .arg(temp_dir.path())
+        .arg("--newer")
+        .arg("1h")
+        .arg("--no-color")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("fresh.txt"));
+
+    // ...and not older than a fixed date in the past
+    Command::cargo_bin("ptlist")
+        .unwrap()
+        .arg("--path")
+        .arg(temp_dir.path())
+        .arg("--older")
+        .arg("2000-01-01")
+        .arg("--no-color")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("fresh.txt").not());
+
+    // Reference file comparison is strict, so the reference itself is excluded
+    Command::cargo_bin("ptlist")
+        .unwrap()
+        .arg("--path")
+        .arg(temp_dir.path())
+        .arg("--newer-than")
+        .arg(temp_dir.path().join("fresh.txt"))
+        .arg("--no-color")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("fresh.txt").not());
+
+    Command::cargo_bin("ptlist")
+        .unwrap()
+        .arg("--path")
+        .arg(temp_dir.path())
+        .arg("--newer")
+        .arg("soon")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Invalid time"));
+}
+
+#[test]
+fn test_permission_filtering() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let temp_dir = TempDir::new().unwrap();
+    let script = temp_dir.path().join("run.sh");
+    File::create(&script).unwrap();
+    std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o755)).unwrap();
+    let notes = temp_dir.path().join("notes.txt");
+    File::create(&notes).unwrap();
+    std::fs::set_permissions(&notes, std::fs::Permissions::from_mode(0o644)).unwrap();
+
+    Command::cargo_bin("ptlist")
+        .unwrap()
+        .arg("--path")
+        .arg(temp_dir.path())
+        .arg("--executable")
+        .arg("--no-color")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("run.sh"))
+        .stdout(predicate::str::contains("notes.txt").not());
+
+    Command::cargo_bin("ptlist")
+        .unwrap()
+        .arg("--path")
+        .arg(temp_dir.path())
+        .arg("--perm")
+        .arg("644")
+        .arg("--no-color")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("notes.txt"))
+        .stdout(predicate::str::contains("run.sh").not());
+
+    Command::cargo_bin("ptlist")
+        .unwrap()
+        .arg("--path")
+        .arg(temp_dir.path())
+        .arg("--perm")
+        .arg("/100")
+        .arg("--no-color")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("run.sh"))
+        .stdout(predicate::str::contains("notes.txt").not());
+}
+
+#[test]
+fn test_size_units() {
+    let temp_dir = TempDir::new().unwrap();
+    let mut small = File::create(temp_dir.path().join("small.bin")).unwrap();
+    small.write_all(&[0; 500]).unwrap();
+    let mut large = File::create(temp_dir.path().join("large.bin")).unwrap();
+    large.write_all(&[0; 2048]).unwrap();
+
+    Command::cargo_bin("ptlist")
+        .unwrap()
+        .arg("--path")
+        .arg(temp_dir.path())
+        .arg("--min-size")
+        .arg("1K")
+        .arg("--no-color")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("large.bin"))
+        .stdout(predicate::str::contains("small.bin").not());
+
+    Command::cargo_bin("ptlist")
+        .unwrap()
+        .arg("--path")
+        .arg(temp_dir.path())
+        .arg("--max-size")
+        .arg("1kB")
+        .arg("--human-readable")
+        .arg("--no-color")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("small.bin"))
+        .stdout(predicate::str::contains("large.bin").not());
+
+    Command::cargo_bin("ptlist")
+        .unwrap()
+        .arg("--path")
+        .arg(temp_dir.path())
+        .arg("--si")
+        .arg("--no-color")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("2.0 kB"));
+
+    Command::cargo_bin("ptlist")
+        .unwrap()
+        .arg("--min-size")
+        .arg("10Q")
+        .assert()
+        .failure();
+}
+
+#[test]
+fn test_content_search() {
+    let temp_dir = TempDir::new().unwrap();
+    let mut todo = File::create(temp_dir.path().join("todo.rs")).unwrap();
+    writeln!(todo, "// TODO: first\n// TODO: second").unwrap();
+    let mut done = File::create(temp_dir.path().join("done.rs")).unwrap();
+    writeln!(done, "fn main() {{}}").unwrap();
+
+    Command::cargo_bin("ptlist")
+        .unwrap()
+        .arg("--path")
+        .arg(temp_dir.path())
+        .arg("--contains")
+        .arg("TODO")
+        .arg("--count-matches")
+        .arg("--format")
+        .arg("json")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("todo.rs"))
+        .stdout(predicate::str::contains("\"match_count\": 2"))
+        .stdout(predicate::str::contains("done.rs").not());
+
+    Command::cargo_bin("ptlist")
+        .unwrap()
+        .arg("--path")
+        .arg(temp_dir.path())
+        .arg("--contains")
+        .arg("fn\\s+main")
+        .arg("--regex")
+        .arg("--no-color")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("done.rs"))
+        .stdout(predicate::str::contains("todo.rs").not());
+}
+
+#[test]
+fn test_empty_filtering() {
+    let temp_dir = TempDir::new().unwrap();
+    File::create(temp_dir.path().join("blank.txt")).unwrap();
+    let mut full = File::create(temp_dir.path().join("full.txt")).unwrap();
+    full.write_all(b"data").unwrap();
+    std::fs::create_dir(temp_dir.path().join("vacant")).unwrap();
+    std::fs::create_dir_all(temp_dir.path().join("nested/inner")).unwrap();
+
+    // Recursive mode finds the empty leaf directory but not its non-empty parent
+    Command::cargo_bin("ptlist")
+        .unwrap()
+        .arg("--path")
+        .arg(temp_dir.path())
+        .arg("--recursive")
+        .arg("--empty")
+        .arg("--no-color")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("blank.txt"))
+        .stdout(predicate::str::contains("vacant"))
+        .stdout(predicate::str::contains("inner"))
+        .stdout(predicate::str::contains("full.txt").not())
+        .stdout(predicate::str::contains("nested").not());
+
+    Command::cargo_bin("ptlist")
+        .unwrap()
+        .arg("--path")
+        .arg(temp_dir.path())
+        .arg("--non-empty")
+        .arg("--no-color")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("full.txt"))
+        .stdout(predicate::str::contains("nested"))
+        .stdout(predicate::str::contains("blank.txt").not())
+        .stdout(predicate::str::contains("vacant").not());
+
+    // --empty-ignore-hidden means nothing on its own, and the two filters exclude each other
+    Command::cargo_bin("ptlist")
+        .unwrap()
+        .arg("--empty-ignore-hidden")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--empty"));
+    Command::cargo_bin("ptlist")
+        .unwrap()
+        .args(["--empty", "--non-empty"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("cannot be used with"));
+}
+
+#[test]
+fn test_multi_key_sorting() {
+    let temp_dir = TempDir::new().unwrap();
+    for (name, size) in [("b.txt", 10), ("a.txt", 10), ("c.txt", 50)] {
+        let mut file = File::create(temp_dir.path().join(name)).unwrap();
+        file.write_all(&vec![0; size]).unwrap();
+    }
+    std::fs::create_dir(temp_dir.path().join("zdir")).unwrap();
+
+    let output = Command::cargo_bin("ptlist")
+        .unwrap()
+        .arg("--path")
+        .arg(temp_dir.path())
+        .arg("--sort")
+        .arg("size:desc,name")
+        .arg("--group-dirs")
+        .arg("first")
+        .arg("--format")
+        .arg("long")
+        .arg("--no-color")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let position = |name: &str| stdout.find(name).unwrap();
+    assert!(position("zdir") < position("c.txt"));
+    assert!(position("c.txt") < position("a.txt"));
+    assert!(position("a.txt") < position("b.txt"));
+
+    Command::cargo_bin("ptlist")
+        .unwrap()
+        .arg("--sort")
+        .arg("size:sideways")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "Invalid sort field: size:sideways",
+        ));
+}
+
+#[test]
+fn test_all_sort_fields() {
+    let fields = [
+        "name",
+        "size",
+        "modified",
+        "created",
+        "accessed",
+        "type",
+        "extension",
+        "owner",
+        "group",
+        "permissions",
+        "depth",
+        "path",
+        "inode",
+        "name-length",
+        "none",
+    ];
+    let temp_dir = TempDir::new().unwrap();
+    std::fs::create_dir(temp_dir.path().join("sub")).unwrap();
+    File::create(temp_dir.path().join("sub/inner.rs")).unwrap();
+    File::create(temp_dir.path().join("top.txt")).unwrap();
+
+    for field in fields {
+        Command::cargo_bin("ptlist")
+            .unwrap()
+            .arg("--path")
+            .arg(temp_dir.path())
+            .arg("--recursive")
+            .arg("--sort")
+            .arg(format!("{}:desc", field))
+            .arg("--no-color")
+            .assert()
+            .success();
+    }
+}
+
+#[test]
+fn test_hierarchical_sorting() {
+    let temp_dir = TempDir::new().unwrap();
+    std::fs::create_dir(temp_dir.path().join("alpha")).unwrap();
+    let mut big = File::create(temp_dir.path().join("alpha/big_inner.bin")).unwrap();
+    big.write_all(&[0; 9000]).unwrap();
+    let mut top = File::create(temp_dir.path().join("top.bin")).unwrap();
+    top.write_all(&[0; 5000]).unwrap();
+
+    let run = |extra: &[&str]| {
+        let output = Command::cargo_bin("ptlist")
+            .unwrap()
+            .arg("--path")
+            .arg(temp_dir.path())
+            .args(["--recursive", "--format", "long", "--sort", "size:desc"])
+            .args(extra)
+            .arg("--no-color")
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        String::from_utf8(output.stdout).unwrap()
+    };
+
+    // Hierarchical by default: top.bin (5000) sorts before the alpha directory,
+    // and alpha's contents directly follow it
+    let stdout = run(&[]);
+    assert!(stdout.find("top.bin").unwrap() < stdout.find("alpha").unwrap());
+    assert!(stdout.find("alpha").unwrap() < stdout.find("big_inner.bin").unwrap());
+
+    // --flat-sort sorts globally, so the nested 9000-byte file comes first
+    let stdout = run(&["--flat-sort"]);
+    assert!(stdout.find("big_inner.bin").unwrap() < stdout.find("top.bin").unwrap());
+}
+
+#[test]
+fn test_grid_format() {
+    let temp_dir = TempDir::new().unwrap();
+    for name in ["a", "b", "c", "d"] {
+        File::create(temp_dir.path().join(name)).unwrap();
+    }
+
+    // Wide enough for one line
+    Command::cargo_bin("ptlist")
+        .unwrap()
+        .arg("--path")
+        .arg(temp_dir.path())
+        .args(["--format", "grid", "--no-color"])
+        .env("COLUMNS", "80")
+        .assert()
+        .success()
+        .stdout("a  b  c  d\n");
+
+    // Two columns, filled top to bottom
+    Command::cargo_bin("ptlist")
+        .unwrap()
+        .arg("--path")
+        .arg(temp_dir.path())
+        .args(["--format", "grid", "--no-color"])
+        .env("COLUMNS", "5")
+        .assert()
+        .success()
+        .stdout("a  c\nb  d\n");
+
+    // Two columns, filled left to right
+    Command::cargo_bin("ptlist")
+        .unwrap()
+        .arg("--path")
+        .arg(temp_dir.path())
+        .args(["--format", "grid", "--across", "--no-color"])
+        .env("COLUMNS", "5")
+        .assert()
+        .success()
+        .stdout("a  b\nc  d\n");
+}
+
+#[test]
+fn test_lines_format() {
+    let temp_dir = TempDir::new().unwrap();
+    std::fs::create_dir(temp_dir.path().join("sub")).unwrap();
+    File::create(temp_dir.path().join("sub/inner.txt")).unwrap();
+    File::create(temp_dir.path().join("with space.txt")).unwrap();
+
+    Command::cargo_bin("ptlist")
+        .unwrap()
+        .arg("--path")
+        .arg(temp_dir.path())
+        .args(["-1", "--recursive", "--sort", "path"])
+        .assert()
+        .success()
+        .stdout("sub\nsub/inner.txt\nwith space.txt\n");
+
+    Command::cargo_bin("ptlist")
+        .unwrap()
+        .arg("--path")
+        .arg(temp_dir.path())
+        .args(["--format", "lines", "--quoting-style", "shell"])
+        .assert()
+        .success()
+        .stdout("sub\n'with space.txt'\n");
+
+    Command::cargo_bin("ptlist")
+        .unwrap()
+        .arg("--path")
+        .arg(temp_dir.path())
+        .arg("--print0")
+        .assert()
+        .success()
+        .stdout("sub\0with space.txt\0");
+
+    let absolute = temp_dir.path().join("sub");
+    Command::cargo_bin("ptlist")
+        .unwrap()
+        .arg("--path")
+        .arg(temp_dir.path())
+        .args(["-1", "--absolute", "--dirs-only"])
+        .assert()
+        .success()
+        .stdout(format!("{}\n", absolute.display()));
+}
+
+#[test]
+fn test_csv_and_tsv_formats() {
+    let temp_dir = TempDir::new().unwrap();
+    let mut file = File::create(temp_dir.path().join("a,\"b\".txt")).unwrap();
+    file.write_all(b"12345").unwrap();
+    File::create(temp_dir.path().join("tab\there")).unwrap();
+
+    Command::cargo_bin("ptlist")
+        .unwrap()
+        .arg("--path")
+        .arg(temp_dir.path())
+        .args(["--format", "csv", "--columns", "name,size,type"])
+        .assert()
+        .success()
+        .stdout("name,size,type\r\n\"a,\"\"b\"\".txt\",5,file\r\ntab\there,0,file\r\n");
+
+    Command::cargo_bin("ptlist")
+        .unwrap()
+        .arg("--path")
+        .arg(temp_dir.path())
+        .args(["--format", "tsv", "--columns", "size,name"])
+        .assert()
+        .success()
+        .stdout("size\tname\n5\ta,\"b\".txt\n0\ttab\\there\n");
+
+    Command::cargo_bin("ptlist")
+        .unwrap()
+        .arg("--path")
+        .arg(temp_dir.path())
+        .args(["--format", "csv"])
+        .assert()
+        .success()
+        .stdout(predicate::str::starts_with(
+            "name,path,type,size,modified,mode,owner,group\r\n",
+        ));
+
+    Command::cargo_bin("ptlist")
+        .unwrap()
+        .arg("--path")
+        .arg(temp_dir.path())
+        .args(["--format", "csv", "--columns", "name,colour"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Invalid column: colour"));
+}
+
+#[test]
+fn test_ndjson_format() {
+    let temp_dir = TempDir::new().unwrap();
+    std::fs::create_dir(temp_dir.path().join("sub")).unwrap();
+    let mut file = File::create(temp_dir.path().join("sub/inner.txt")).unwrap();
+    file.write_all(b"hello").unwrap();
+    File::create(temp_dir.path().join("a.txt")).unwrap();
+
+    // Streamed output is sorted per directory, --flat-sort sorts the whole listing
+    for (extra, expected) in [
+        (&[][..], ["a.txt", "sub", "inner.txt"]),
+        (&["--flat-sort"][..], ["a.txt", "inner.txt", "sub"]),
+    ] {
+        let output = Command::cargo_bin("ptlist")
+            .unwrap()
+            .arg("--path")
+            .arg(temp_dir.path())
+            .args(["--format", "ndjson", "--recursive", "--summary"])
+            .args(extra)
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+
+        let records: Vec<serde_json::Value> = String::from_utf8(output.stdout)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(records.len(), 4);
+        let names: Vec<_> = records[..3].iter().map(|r| r["name"].clone()).collect();
+        assert_eq!(names, expected);
+
+        let total_size: u64 = records[..3]
+            .iter()
+            .map(|r| r["size"].as_u64().unwrap())
+            .sum();
+        assert_eq!(
+            records[3],
+            serde_json::json!({
+                "summary": {"files": 2, "directories": 1, "total_size": total_size, "errors": []}
+            })
+        );
+    }
+}
+
+#[test]
+fn test_json_tree_format() {
+    let temp_dir = TempDir::new().unwrap();
+    std::fs::create_dir_all(temp_dir.path().join("sub/deeper")).unwrap();
+    let mut file = File::create(temp_dir.path().join("sub/deeper/inner.txt")).unwrap();
+    file.write_all(b"hello").unwrap();
+    let mut file = File::create(temp_dir.path().join("top.txt")).unwrap();
+    file.write_all(b"abc").unwrap();
+
+    let output = Command::cargo_bin("ptlist")
+        .unwrap()
+        .arg("--path")
+        .arg(temp_dir.path())
+        .args(["--format", "json-tree", "--recursive", "--dir-totals"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    let document: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let root = &document["tree"];
+    assert_eq!(root["total_size"], 8);
+    assert_eq!(root["file_count"], 2);
+
+    let children = root["children"].as_array().unwrap();
+    assert_eq!(children.len(), 2);
+    assert_eq!(children[0]["name"], "sub");
+    assert_eq!(children[0]["total_size"], 5);
+    assert_eq!(children[0]["children"][0]["name"], "deeper");
+    assert_eq!(
+        children[0]["children"][0]["children"][0]["name"],
+        "inner.txt"
+    );
+    assert_eq!(children[1]["name"], "top.txt");
+    assert!(children[1].get("children").is_none());
+    assert!(children[1].get("total_size").is_none());
+
+    // Totals are only added on request
+    let output = Command::cargo_bin("ptlist")
+        .unwrap()
+        .arg("--path")
+        .arg(temp_dir.path())
+        .args(["--format", "json-tree"])
+        .output()
+        .unwrap();
+    let document: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let root = &document["tree"];
+    assert!(root.get("total_size").is_none());
+    assert_eq!(root["children"][0]["children"], serde_json::json!([]));
+
+    // Without --recursive, directories whose contents were not listed get no totals
+    let output = Command::cargo_bin("ptlist")
+        .unwrap()
+        .arg("--path")
+        .arg(temp_dir.path())
+        .args(["--format", "json-tree", "--dir-totals"])
+        .output()
+        .unwrap();
+    let document: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let root = &document["tree"];
+    assert_eq!(root["total_size"], 3);
+    assert_eq!(root["children"][0]["name"], "sub");
+    assert!(root["children"][0].get("total_size").is_none());
+    assert!(root["children"][0].get("file_count").is_none());
+}
+
+#[test]
+fn test_json_envelope_and_schema() {
+    let temp_dir = TempDir::new().unwrap();
+    let mut file = File::create(temp_dir.path().join("run.sh")).unwrap();
+    file.write_all(b"#!/bin/sh\n").unwrap();
+    std::fs::set_permissions(
+        temp_dir.path().join("run.sh"),
+        std::os::unix::fs::PermissionsExt::from_mode(0o755),
+    )
+    .unwrap();
+
+    let output = Command::cargo_bin("ptlist")
+        .unwrap()
+        .arg("--path")
+        .arg(temp_dir.path())
+        .args(["--format", "json"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    let document: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(document["schema_version"], 1);
+    assert_eq!(document["tool"]["version"], env!("CARGO_PKG_VERSION"));
+    assert_eq!(document["root"], temp_dir.path().to_str().unwrap());
+    assert_eq!(document["options"]["format"], "json");
+
+    let entry = &document["entries"][0];
+    assert_eq!(entry["kind"], "file");
+    assert_eq!(entry["size"], 10);
+    assert_eq!(entry["mode"]["octal"], "0755");
+    assert_eq!(entry["mode"]["symbolic"], "-rwxr-xr-x");
+    assert!(entry["modified"]["epoch_ns"].is_i64());
+    assert!(entry["modified"]["rfc3339"].is_string());
+    assert!(entry["uid"].is_u64());
+
+    let output = Command::cargo_bin("ptlist")
+        .unwrap()
+        .arg("--json-schema")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let schema: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(schema["properties"]["schema_version"]["const"], 1);
+
+    // The options object has exactly the fields the schema documents
+    let options = &schema["properties"]["options"];
+    let mut required: Vec<&str> = options["required"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|key| key.as_str().unwrap())
+        .collect();
+    required.sort();
+    let mut written: Vec<&str> = document["options"]
+        .as_object()
+        .unwrap()
+        .keys()
+        .map(String::as_str)
+        .collect();
+    written.sort();
+    assert_eq!(written, required);
+    for key in required {
+        assert!(options["properties"].get(key).is_some(), "{}", key);
+    }
+}
+
+#[test]
+fn test_json_output_with_non_utf8_name() {
+    use std::ffi::OsStr;
+    use std::os::unix::ffi::OsStrExt;
+
+    let temp_dir = TempDir::new().unwrap();
+    File::create(temp_dir.path().join(OsStr::from_bytes(b"bad\xff"))).unwrap();
+    File::create(temp_dir.path().join("good.txt")).unwrap();
+
+    for format in ["json", "json-tree"] {
+        let output = Command::cargo_bin("ptlist")
+            .unwrap()
+            .arg("--path")
+            .arg(temp_dir.path())
+            .args(["--format", format])
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "{format}");
+
+        let document: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+        let entries = if format == "json" {
+            &document["entries"]
+        } else {
+            &document["tree"]["children"]
+        };
+        assert_eq!(entries[0]["name"], "bad\u{fffd}");
+        assert!(
+            entries[0]["path"]
+                .as_str()
+                .unwrap()
+                .ends_with("/bad\u{fffd}")
+        );
+        assert_eq!(entries[1]["name"], "good.txt");
+    }
+}
+
+#[test]
+fn test_template_format() {
+    let temp_dir = TempDir::new().unwrap();
+    let mut file = File::create(temp_dir.path().join("data.bin")).unwrap();
+    file.write_all(&[0; 2048]).unwrap();
+    std::fs::create_dir(temp_dir.path().join("docs")).unwrap();
+
+    Command::cargo_bin("ptlist")
+        .unwrap()
+        .arg("--path")
+        .arg(temp_dir.path())
+        .args([
+            "--format",
+            "template",
+            "--template",
+            "[{name:<8}|{size:>8h}]{?dir}/{/}",
+            "--files-only",
+        ])
+        .assert()
+        .success()
+        .stdout("[data.bin| 2.0 KiB]\n");
+
+    Command::cargo_bin("ptlist")
+        .unwrap()
+        .arg("--path")
+        .arg(temp_dir.path())
+        .args(["--format", "template", "--template", "{name}{?dir}/{/}"])
+        .args(["--no-color", "--sort", "name"])
+        .assert()
+        .success()
+        .stdout("data.bin\ndocs/\n");
+
+    Command::cargo_bin("ptlist")
+        .unwrap()
+        .arg("--path")
+        .arg(temp_dir.path())
+        .args(["--format", "template", "--template", "{colour}"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("unknown field 'colour'"));
+
+    Command::cargo_bin("ptlist")
+        .unwrap()
+        .arg("--path")
+        .arg(temp_dir.path())
+        .args(["--format", "template"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("requires --template"));
+}
+
+#[test]
+fn test_configurable_columns() {
+    let temp_dir = TempDir::new().unwrap();
+    std::fs::create_dir(temp_dir.path().join("sub")).unwrap();
+    File::create(temp_dir.path().join("sub/inner.txt")).unwrap();
+
+    Command::cargo_bin("ptlist")
+        .unwrap()
+        .arg("--path")
+        .arg(temp_dir.path())
+        .args([
+            "--format",
+            "long",
+            "--no-color",
+            "--recursive",
+            "--files-only",
+        ])
+        .args(["--columns", "depth,links,name"])
+        .assert()
+        .success()
+        .stdout(" Depth Links Name\n ---------------------\n     1     1 inner.txt\n");
+
+    Command::cargo_bin("ptlist")
+        .unwrap()
+        .arg("--path")
+        .arg(temp_dir.path())
+        .args(["--format", "long", "--no-color"])
+        .args([
+            "--add-columns",
+            "inode",
+            "--hide-columns",
+            "owner,group,modified",
+        ])
+        .assert()
+        .success()
+        .stdout(predicate::str::is_match(r"^ Permissions Size +Inode Name\n").unwrap());
+
+    Command::cargo_bin("ptlist")
+        .unwrap()
+        .arg("--path")
+        .arg(temp_dir.path())
+        .args([
+            "--no-color",
+            "--add-columns",
+            "disk-usage",
+            "--hide-columns",
+            "type",
+        ])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Disk Usage"))
+        .stdout(predicate::str::contains("Type").not());
+
+    Command::cargo_bin("ptlist")
+        .unwrap()
+        .arg("--path")
+        .arg(temp_dir.path())
+        .args(["--hide-columns", "colour"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Invalid column: colour"));
+}
+
+#[test]
+fn test_closed_pipe() {
+    use std::io::{BufRead, BufReader};
+    use std::process::{Command as StdCommand, Stdio};
+
+    // Enough output to fill the pipe buffer, so ptlist is still writing when
+    // the reader goes away
+    let temp_dir = TempDir::new().unwrap();
+    for i in 0..3000 {
+        File::create(
+            temp_dir
+                .path()
+                .join(format!("file-with-a-rather-long-name-{:04}.txt", i)),
+        )
+        .unwrap();
+    }
+
+    for format in ["lines", "long", "ndjson", "json"] {
+        let mut child = StdCommand::new(assert_cmd::cargo::cargo_bin("ptlist"))
+            .arg("--path")
+            .arg(temp_dir.path())
+            .args(["--no-color", "--format", format])
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .unwrap();
+
+        // Read one line and close the pipe, like `ptlist | head -n 1`
+        let mut first_line = String::new();
+        BufReader::new(child.stdout.take().unwrap())
+            .read_line(&mut first_line)
+            .unwrap();
+        assert!(!first_line.is_empty());
+
+        let output = child.wait_with_output().unwrap();
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert!(output.status.success(), "{}: {}", format, stderr);
+        assert!(stderr.is_empty(), "{}: {}", format, stderr);
+    }
+}
+
+#[test]
+fn test_html_format() {
+    let temp_dir = TempDir::new().unwrap();
+    std::fs::create_dir(temp_dir.path().join("docs")).unwrap();
+    File::create(temp_dir.path().join("docs").join("a&b.md"))
+        .unwrap()
+        .write_all(b"hello")
+        .unwrap();
+
+    Command::cargo_bin("ptlist")
+        .unwrap()
+        .arg("--path")
+        .arg(temp_dir.path())
+        .args(["-r", "--format", "html", "--columns", "name,size"])
+        .assert()
+        .success()
+        .stdout(predicate::str::starts_with("<!DOCTYPE html>"))
+        .stdout(predicate::str::contains(
+            "<th>Name</th><th class=\"num\">Size</th>",
+        ))
+        .stdout(predicate::str::contains("<td>a&amp;b.md</td>"))
+        .stdout(predicate::str::contains(
+            "<td>md</td><td class=\"num\">1</td>",
+        ))
+        .stdout(predicate::str::contains("<details open>"))
+        .stdout(predicate::str::contains("</html>"));
+    // The report stays a single document with --summary
+    Command::cargo_bin("ptlist")
+        .unwrap()
+        .arg("--path")
+        .arg(temp_dir.path())
+        .args(["--format", "html", "--summary"])
+        .assert()
+        .success()
+        .stdout(predicate::str::starts_with("<!DOCTYPE html>"))
+        .stdout(predicate::str::contains("Summary:").not());
+}
+
+#[test]
+fn test_markdown_formats() {
+    let temp_dir = TempDir::new().unwrap();
+    std::fs::create_dir(temp_dir.path().join("docs")).unwrap();
+    File::create(temp_dir.path().join("docs").join("a|b.md")).unwrap();
+
+    Command::cargo_bin("ptlist")
+        .unwrap()
+        .arg("--path")
+        .arg(temp_dir.path())
+        .args(["-r", "--format", "markdown", "--columns", "name,type"])
+        .assert()
+        .success()
+        .stdout(
+            "| Name    | Type |\n|---------|------|\n| a\\|b.md | File |\n| docs    | Dir  |\n",
+        );
+
+    Command::cargo_bin("ptlist")
+        .unwrap()
+        .arg("--path")
+        .arg(temp_dir.path())
+        .args(["-r", "--format", "markdown-tree"])
+        .assert()
+        .success()
+        .stdout("- **docs/**\n  - a\\|b.md\n");
+}
+
+#[test]
+fn test_svg_chart_formats() {
+    let temp_dir = TempDir::new().unwrap();
+    std::fs::create_dir(temp_dir.path().join("src")).unwrap();
+    File::create(temp_dir.path().join("src").join("main.rs"))
+        .unwrap()
+        .write_all(&[b'x'; 3000])
+        .unwrap();
+    File::create(temp_dir.path().join("notes.txt"))
+        .unwrap()
+        .write_all(&[b'x'; 1000])
+        .unwrap();
+
+    // Charts walk the whole tree even without --recursive
+    for format in ["treemap-svg", "sunburst-svg"] {
+        Command::cargo_bin("ptlist")
+            .unwrap()
+            .arg("--path")
+            .arg(temp_dir.path())
+            .args(["--format", format])
+            .assert()
+            .success()
+            .stdout(predicate::str::starts_with("<svg xmlns="))
+            .stdout(predicate::str::contains("main.rs — 2.9 KiB</title>"))
+            .stdout(predicate::str::contains(
+                "src/ — 2.9 KiB in 1 files</title>",
+            ));
+    }
+
+    // Kind colors do not depend on terminal colors, and --summary keeps the SVG valid
+    Command::cargo_bin("ptlist")
+        .unwrap()
+        .arg("--path")
+        .arg(temp_dir.path())
+        .args(["--format", "treemap-svg", "--no-color", "--summary"])
+        .assert()
+        .success()
+        .stdout(predicate::str::starts_with("<svg xmlns="))
+        .stdout(predicate::str::contains("fill=\"#e06666\""))
+        .stdout(predicate::str::contains("Summary:").not());
+
+    Command::cargo_bin("ptlist")
+        .unwrap()
+        .arg("--path")
+        .arg(temp_dir.path())
+        .args(["--format", "treemap-svg", "--chart-color", "rainbow"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Invalid chart color: rainbow"));
+}
+
+#[test]
+fn test_size_bars() {
+    let temp_dir = TempDir::new().unwrap();
+    std::fs::create_dir(temp_dir.path().join("big")).unwrap();
+    File::create(temp_dir.path().join("big").join("data.bin"))
+        .unwrap()
+        .write_all(&[0; 3072])
+        .unwrap();
+    File::create(temp_dir.path().join("small.txt"))
+        .unwrap()
+        .write_all(&[0; 1024])
+        .unwrap();
+
+    Command::cargo_bin("ptlist")
+        .unwrap()
+        .arg("--path")
+        .arg(temp_dir.path())
+        .args(["-r", "--no-color", "--format", "tree", "--bars", "-H"])
+        .assert()
+        .success()
+        .stdout(predicate::str::is_match(r"(?m)^ 75\.0% █+ +├── big \(3\.0 KiB\)$").unwrap())
+        .stdout(
+            predicate::str::is_match(r"(?m)^100\.0% █+ +│   └── data\.bin \(3\.0 KiB\)$").unwrap(),
+        )
+        .stdout(
+            predicate::str::is_match(r"(?m)^ 25\.0% █+ +└── small\.txt \(1\.0 KiB\)$").unwrap(),
+        );
+
+    Command::cargo_bin("ptlist")
+        .unwrap()
+        .arg("--path")
+        .arg(temp_dir.path())
+        .args(["-r", "--no-color", "--format", "long", "--bars"])
+        .args([
+            "--columns",
+            "size,name",
+            "--sort",
+            "size:desc",
+            "--flat-sort",
+        ])
+        .assert()
+        .success()
+        .stdout(predicate::str::is_match(r"(?m)^ 3072 █+ +100\.0% data\.bin$").unwrap())
+        .stdout(predicate::str::is_match(r"(?m)^ 1024 █+ +25\.0% small\.txt$").unwrap());
+
+    // Without --recursive nothing below a directory is listed, so it keeps its own size
+    Command::cargo_bin("ptlist")
+        .unwrap()
+        .arg("--path")
+        .arg(temp_dir.path())
+        .args(["--no-color", "--format", "long", "--bars"])
+        .args(["--columns", "size,name"])
+        .assert()
+        .success()
+        .stdout(predicate::str::is_match(r"(?m)^ *[1-9]\d* .*\d+\.\d% big$").unwrap())
+        .stdout(predicate::str::is_match(r"(?m) 0\.0% big$").unwrap().not());
+}
+
+#[test]
+fn test_tree_options() {
+    let temp_dir = TempDir::new().unwrap();
+    std::fs::create_dir(temp_dir.path().join("docs")).unwrap();
+    for i in 0..5 {
+        File::create(temp_dir.path().join("docs").join(format!("{}.md", i)))
+            .unwrap()
+            .write_all(&[b'x'; 100])
+            .unwrap();
+    }
+    File::create(temp_dir.path().join("a.txt")).unwrap();
+
+    Command::cargo_bin("ptlist")
+        .unwrap()
+        .arg("--path")
+        .arg(temp_dir.path())
+        .args(["-r", "--no-color", "--format", "tree", "--dir-totals"])
+        .args([
+            "--tree-collapse",
+            "2",
+            "--tree-dirs-first",
+            "--charset",
+            "ascii",
+        ])
+        .assert()
+        .success()
+        .stdout(
+            "|-- docs (500 B, 5 files)\n\
+             |   |-- 0.md\n\
+             |   |-- 1.md\n\
+             |   `-- ... 3 more files (300 B)\n\
+             `-- a.txt\n",
+        );
+
+    Command::cargo_bin("ptlist")
+        .unwrap()
+        .arg("--path")
+        .arg(temp_dir.path())
+        .args(["--format", "tree", "--charset", "ebcdic"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Invalid charset: ebcdic"));
+
+    // The charset is checked for every format and draws the bars of long output
+    Command::cargo_bin("ptlist")
+        .unwrap()
+        .arg("--path")
+        .arg(temp_dir.path())
+        .args(["--format", "table", "--charset", "bogus"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Invalid charset: bogus"));
+
+    Command::cargo_bin("ptlist")
+        .unwrap()
+        .arg("--path")
+        .arg(temp_dir.path())
+        .args(["--no-color", "--format", "long", "--bars", "--charset", "ascii"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("#"))
+        .stdout(predicate::str::contains("█").not());
+}
+
+#[test]
+#[cfg(feature = "tui")]
+fn test_tui_requires_terminal() {
+    Command::cargo_bin("ptlist")
+        .unwrap()
+        .arg("tui")
+        .assert()
+        .failure()
+        .stdout(predicate::str::is_empty())
+        .stderr(predicate::str::contains("needs a terminal"));
+}
+
+#[test]
+fn test_depth_is_relative_to_listed_directory() {
+    let temp_dir = TempDir::new().unwrap();
+    std::fs::create_dir_all(temp_dir.path().join("sub/deeper")).unwrap();
+    File::create(temp_dir.path().join("sub/deeper/inner.txt")).unwrap();
+    File::create(temp_dir.path().join("top.txt")).unwrap();
+
+    // Depth counts levels below --path however deep --path itself is
+    Command::cargo_bin("ptlist")
+        .unwrap()
+        .arg("--path")
+        .arg(temp_dir.path())
+        .args(["-r", "--flat-sort", "--sort", "depth:desc,name"])
+        .args(["--format", "tsv", "--columns", "depth,name"])
+        .assert()
+        .success()
+        .stdout("depth\tname\n2\tinner.txt\n1\tdeeper\n0\tsub\n0\ttop.txt\n");
+}
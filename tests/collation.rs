@@ -0,0 +1,153 @@
+use proptest::prelude::*;
+use ptlist::Collation;
+use ptlist::collation::{natural_cmp, version_cmp};
+use std::cmp::Ordering;
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+const ALL_COLLATIONS: [Collation; 5] = [
+    Collation::Natural,
+    Collation::Version,
+    Collation::Locale,
+    Collation::Ascii,
+    Collation::IgnoreCase,
+];
+
+/// File-name-like strings mixing letters, digit runs, dots, tildes and accents
+fn name() -> impl Strategy<Value = String> {
+    "[a-cA-C0-9._~é-]{0,12}"
+}
+
+/// Reference model for natural ordering: split into text and number tokens,
+/// compare numbers by value, then fall back to leading-zero counts
+fn natural_reference(a: &str, b: &str) -> Ordering {
+    #[derive(PartialEq, Eq)]
+    enum Token {
+        /// First raw digit, then the value as (length, digits) without leading zeros
+        Number(char, usize, String),
+        Char(char),
+    }
+
+    impl Ord for Token {
+        fn cmp(&self, other: &Self) -> Ordering {
+            match (self, other) {
+                (Token::Number(_, a_len, a), Token::Number(_, b_len, b)) => {
+                    a_len.cmp(b_len).then_with(|| a.cmp(b))
+                }
+                // A number against a character compares its first raw digit
+                (Token::Number(a, ..), Token::Char(b)) | (Token::Char(a), Token::Number(b, ..)) => {
+                    a.cmp(b)
+                }
+                (Token::Char(a), Token::Char(b)) => a.cmp(b),
+            }
+        }
+    }
+
+    impl PartialOrd for Token {
+        fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+            Some(self.cmp(other))
+        }
+    }
+
+    fn tokenize(s: &str) -> (Vec<Token>, Vec<usize>) {
+        let mut tokens = Vec::new();
+        let mut zeros = Vec::new();
+        let mut chars = s.chars().peekable();
+        while let Some(&c) = chars.peek() {
+            if c.is_ascii_digit() {
+                let mut digits = String::new();
+                while let Some(&d) = chars.peek().filter(|d| d.is_ascii_digit()) {
+                    digits.push(d);
+                    chars.next();
+                }
+                let value = digits.trim_start_matches('0').to_string();
+                zeros.push(digits.len());
+                tokens.push(Token::Number(c, value.len(), value));
+            } else {
+                tokens.push(Token::Char(c));
+                chars.next();
+            }
+        }
+        (tokens, zeros)
+    }
+
+    let (a_tokens, a_zeros) = tokenize(a);
+    let (b_tokens, b_zeros) = tokenize(b);
+    a_tokens.cmp(&b_tokens).then(a_zeros.cmp(&b_zeros))
+}
+
+/// Sort with GNU `sort -V` in the C locale, if it is available
+fn gnu_version_sort(names: &[String]) -> Option<Vec<String>> {
+    let mut child = Command::new("sort")
+        .arg("-V")
+        .env("LC_ALL", "C")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .ok()?;
+    {
+        let stdin = child.stdin.as_mut()?;
+        for name in names {
+            writeln!(stdin, "{}", name).ok()?;
+        }
+    }
+    let output = child.wait_with_output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    Some(
+        String::from_utf8(output.stdout)
+            .ok()?
+            .lines()
+            .map(String::from)
+            .collect(),
+    )
+}
+
+proptest! {
+    #[test]
+    fn collations_are_total_orders(a in name(), b in name(), c in name()) {
+        for collation in ALL_COLLATIONS {
+            prop_assert_eq!(collation.compare(&a, &a), Ordering::Equal);
+            prop_assert_eq!(collation.compare(&a, &b), collation.compare(&b, &a).reverse());
+            if collation.compare(&a, &b) != Ordering::Greater
+                && collation.compare(&b, &c) != Ordering::Greater
+            {
+                prop_assert_ne!(collation.compare(&a, &c), Ordering::Greater);
+            }
+            if a != b {
+                prop_assert_ne!(collation.compare(&a, &b), Ordering::Equal);
+            }
+        }
+    }
+
+    #[test]
+    fn natural_matches_reference(a in name(), b in name()) {
+        prop_assert_eq!(natural_cmp(&a, &b), natural_reference(&a, &b));
+    }
+
+    #[test]
+    fn natural_handles_long_digit_runs(x in "[0-9]{1,40}", y in "[0-9]{1,40}") {
+        let a = format!("file{}", x);
+        let b = format!("file{}", y);
+        prop_assert_eq!(natural_cmp(&a, &b), natural_reference(&a, &b));
+    }
+
+    #[test]
+    fn version_matches_gnu_sort(names in prop::collection::vec("[a-c0-9._~-]{1,10}", 1..12)) {
+        let Some(expected) = gnu_version_sort(&names) else {
+            return Ok(());
+        };
+        let mut actual = names.clone();
+        actual.sort_by(|a, b| Collation::Version.compare(a, b));
+        prop_assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn version_orders_plain_numbers_numerically(prefix in "[a-z]{1,5}", x in 0u64..100_000, y in 0u64..100_000) {
+        let a = format!("{}-{}.tar.gz", prefix, x);
+        let b = format!("{}-{}.tar.gz", prefix, y);
+        prop_assert_eq!(version_cmp(&a, &b), x.cmp(&y));
+    }
+}